@@ -1,107 +1,481 @@
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::time::Instant;
 
+use crate::budget::DepthLimit;
+use crate::cost::{Cost, CostOverflow};
 use crate::graph::HeuristicGraph;
 use crate::graph::WeightedGraph;
-use crate::graphsearcher::GraphSearcher;
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
 use crate::heapelement::HeapElement;
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
 use std::fmt::Debug;
 
-pub struct AStar<G: WeightedGraph + HeuristicGraph> {
-    distances: HashMap<G::Node, i64>,
+pub struct AStar<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost = i64> {
+    distances: HashMap<G::Node, C>,
     parents: HashMap<G::Node, G::Node>,
-    max_frontier: usize,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    /// nodes expanded at their current distance.
+    closed: HashSet<G::Node>,
+    frontier: BinaryHeap<HeapElement<G::Node, C>>,
     solution: Option<G::Node>,
+    /// the edge whose cost overflowed, ending the search.
+    overflow: Option<CostOverflow<G::Node>>,
+    weight: f64,
+    depth_limit: DepthLimit,
 }
 
-impl<G: WeightedGraph + HeuristicGraph> Debug for AStar<G> {
+/// A solution reported by [`AStar::search_anytime`], with the cost of its path
+/// and the factor by which that cost is guaranteed to be within the optimum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnytimeSolution<N, C = i64> {
+    pub goal: N,
+    pub path: Vec<N>,
+    pub cost: C,
+    pub bound: f64,
+}
+
+/// The best solution ARA* found, or the edge whose cost overflowed.
+pub type AnytimeResult<N, C = i64> = Result<Option<AnytimeSolution<N, C>>, CostOverflow<N>>;
+
+/// Orders nodes by g + w * h, or `None` if that overflows. `Cost::scale`
+/// rounds down, keeping the inflated heuristic below w * h, which is what the
+/// suboptimality bound relies on.
+#[allow(clippy::float_cmp)]
+fn weighted_priority<C: Cost>(cost: C, heuristic: C, weight: f64) -> Option<C> {
+    if weight == 1.0 {
+        cost.checked_add(heuristic)
+    } else {
+        cost.checked_add(heuristic.scale(weight))
+    }
+}
+
+/// Expands nodes in order of g + h until one is a goal, reporting each
+/// expansion to `observer`. An overflow ends the search, since f-values past
+/// it can't be trusted to find the nearest goal.
+fn a_star_search<G, C, O>(
+    graph: &G,
+    root: G::Node,
+    observer: &mut O,
+) -> Result<Option<G::Node>, CostOverflow<G::Node>>
+where
+    G: WeightedGraph<C> + HeuristicGraph<C>,
+    C: Cost,
+    O: SearchObserver<G>,
+{
+    let mut distances = HashMap::new();
+    let mut closed = HashSet::new();
+    let mut frontier = BinaryHeap::new();
+
+    let heuristic = graph.heuristic(root);
+    if heuristic == C::MAX {
+        return Ok(None);
+    }
+    distances.insert(root, C::ZERO);
+    frontier.push(HeapElement::new(root, heuristic));
+
+    while let Some(HeapElement { node, .. }) = frontier.pop() {
+        if graph.is_goal(node) {
+            observer.on_goal(node);
+            return Ok(Some(node));
+        }
+        // skip entries left behind when a node was reached more cheaply.
+        if !closed.insert(node) {
+            continue;
+        }
+        observer.on_expand(node);
+        let cost_to_node = distances[&node];
+        let mut overflow = None;
+        graph.for_each_weighted_successor(node, |_, child, weight| {
+            if overflow.is_some() {
+                return;
+            }
+            observer.on_generate(node, child);
+            let heuristic = graph.heuristic(child);
+            if heuristic == C::MAX {
+                observer.on_prune(child);
+                return;
+            }
+            let priced = cost_to_node.checked_add(weight).and_then(|cost_to_child| {
+                Some((cost_to_child, cost_to_child.checked_add(heuristic)?))
+            });
+            let Some((cost_to_child, f)) = priced else {
+                overflow = Some(CostOverflow {
+                    from: node,
+                    to: child,
+                });
+                return;
+            };
+            if distances
+                .get(&child)
+                .is_none_or(|&known| cost_to_child < known)
+            {
+                if closed.remove(&child) {
+                    observer.on_reopen(child);
+                }
+                distances.insert(child, cost_to_child);
+                frontier.push(HeapElement::new(child, f));
+            } else {
+                observer.on_duplicate(child);
+            }
+        });
+        if let Some(overflow) = overflow {
+            return Err(overflow);
+        }
+    }
+    Ok(None)
+}
+
+impl<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost> Debug for AStar<G, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "AStar")
     }
 }
 
-impl<G: WeightedGraph + HeuristicGraph> AStar<G> {
+impl<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost> AStar<G, C> {
     #[must_use]
     pub fn new() -> Self {
         Self {
             distances: HashMap::new(),
             parents: HashMap::new(),
-            max_frontier: 1,
+            parent_edges: HashMap::new(),
+            closed: HashSet::new(),
+            frontier: BinaryHeap::new(),
             solution: None,
+            overflow: None,
+            weight: 1.0,
+            depth_limit: DepthLimit::new(),
         }
     }
 
-    pub const fn max_frontier(&self) -> usize {
-        self.max_frontier
+    /// Creates a weighted A* searcher, ordering nodes by g + w * h. With an
+    /// admissible heuristic the solution costs at most w times the optimum.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is less than 1.
+    #[must_use]
+    pub fn with_weight(weight: f64) -> Self {
+        assert!(weight >= 1.0, "heuristic weight must be at least 1");
+        Self {
+            weight,
+            ..Self::new()
+        }
     }
 
-    pub const fn distances(&self) -> &HashMap<G::Node, i64> {
+    pub const fn distances(&self) -> &HashMap<G::Node, C> {
         &self.distances
     }
+
+    pub const fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Finds the nearest goal from `root` without keeping any search state.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending edge if the cost of a route overflows.
+    pub fn try_search(graph: &G, root: G::Node) -> Result<Option<G::Node>, CostOverflow<G::Node>> {
+        a_star_search(graph, root, &mut ())
+    }
+
+    /// Anytime repairing A* (ARA*).
+    ///
+    /// Starts from this searcher's weight and finds a fast first solution, then
+    /// lowers the weight by `weight_step` towards 1, reusing previous search
+    /// effort, until the weight reaches 1 or `deadline` passes. Every strictly
+    /// better solution is passed to `on_solution` as it is found, and the best
+    /// one is returned and made available through `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending edge if a path cost or priority overflows. A
+    /// node whose own priority overflows is given as both ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight_step` is not positive.
+    pub fn search_anytime(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        weight_step: f64,
+        deadline: Option<Instant>,
+        mut on_solution: impl FnMut(&AnytimeSolution<G::Node, C>),
+    ) -> AnytimeResult<G::Node, C> {
+        assert!(weight_step > 0.0, "weight step must be positive");
+        self.distances.clear();
+        self.parents.clear();
+        self.parent_edges.clear();
+        self.solution = None;
+        self.overflow = None;
+
+        let mut weight = self.weight;
+        let mut best: Option<AnytimeSolution<G::Node, C>> = None;
+        let mut incumbent = graph.is_goal(root).then_some(root);
+        let mut open = HashSet::new();
+        let mut inconsistent = HashSet::new();
+        let mut closed = HashSet::new();
+
+        self.distances.insert(root, C::ZERO);
+        if graph.heuristic(root) != C::MAX {
+            open.insert(root);
+        }
+
+        loop {
+            let mut frontier = open
+                .iter()
+                .map(|&n| {
+                    weighted_priority(self.distances[&n], graph.heuristic(n), weight)
+                        .map(|key| HeapElement::new(n, key))
+                        .ok_or(CostOverflow { from: n, to: n })
+                })
+                .collect::<Result<BinaryHeap<_>, _>>()?;
+            closed.clear();
+
+            // improve the current solution under the current weight.
+            let mut timed_out = false;
+            while let Some(&HeapElement { node, cost: key }) = frontier.peek() {
+                if incumbent.is_some_and(|n| key >= self.distances[&n]) {
+                    break;
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    timed_out = true;
+                    break;
+                }
+                frontier.pop();
+                let cost_to_node = self.distances[&node];
+                // skip stale heap entries.
+                if !open.contains(&node)
+                    || Some(key) != weighted_priority(cost_to_node, graph.heuristic(node), weight)
+                {
+                    continue;
+                }
+                open.remove(&node);
+                closed.insert(node);
+                for (edge, child, edge_cost) in graph.weighted_successors(node) {
+                    let overflow = CostOverflow {
+                        from: node,
+                        to: child,
+                    };
+                    let heuristic = graph.heuristic(child);
+                    if heuristic == C::MAX {
+                        continue;
+                    }
+                    let cost_to_child = cost_to_node.checked_add(edge_cost).ok_or(overflow)?;
+                    if self
+                        .distances
+                        .get(&child)
+                        .is_none_or(|&known| cost_to_child < known)
+                    {
+                        let key =
+                            weighted_priority(cost_to_child, heuristic, weight).ok_or(overflow)?;
+                        self.parents.insert(child, node);
+                        self.parent_edges.insert(child, edge);
+                        if graph.is_goal(child)
+                            && incumbent.is_none_or(|n| cost_to_child < self.distances[&n])
+                        {
+                            incumbent = Some(child);
+                        }
+                        self.distances.insert(child, cost_to_child);
+                        if closed.contains(&child) {
+                            inconsistent.insert(child);
+                        } else {
+                            open.insert(child);
+                            frontier.push(HeapElement::new(child, key));
+                        }
+                    }
+                }
+            }
+
+            if let Some(goal) = incumbent {
+                self.solution = Some(goal);
+                let path = self.path().unwrap_or_default();
+                let cost = self.distances[&goal];
+                if best.as_ref().is_none_or(|b| cost < b.cost) {
+                    let lower_bound = self.min_f(graph, open.iter().chain(&inconsistent));
+                    let solution = AnytimeSolution {
+                        goal,
+                        path,
+                        cost,
+                        bound: suboptimality_bound(weight, cost, lower_bound),
+                    };
+                    on_solution(&solution);
+                    best = Some(solution);
+                }
+            }
+
+            if timed_out || weight <= 1.0 {
+                return Ok(best);
+            }
+            weight = f64::max(1.0, weight - weight_step);
+            open.extend(inconsistent.drain());
+        }
+    }
+
+    /// The smallest unweighted f-value among `nodes`, which must all have been
+    /// reached. Values too large for the cost type count as `C::MAX`.
+    fn min_f<'a>(&self, graph: &G, nodes: impl Iterator<Item = &'a G::Node>) -> Option<C>
+    where
+        G::Node: 'a,
+    {
+        nodes
+            .map(|&n| {
+                let h = graph.heuristic(n);
+                self.distances[&n].checked_add(h).unwrap_or(C::MAX)
+            })
+            .min()
+    }
+}
+
+/// The suboptimality bound ARA* can prove for a solution: the current weight,
+/// tightened by comparing the cost against the smallest unweighted f-value
+/// still waiting to be expanded.
+fn suboptimality_bound<C: Cost>(weight: f64, cost: C, lower_bound: Option<C>) -> f64 {
+    match lower_bound {
+        None => 1.0,
+        Some(f) if f > C::ZERO => f64::max(1.0, f64::min(weight, cost.to_f64() / f.to_f64())),
+        Some(_) => weight,
+    }
 }
 
-impl<G: WeightedGraph + HeuristicGraph> Default for AStar<G> {
+impl<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost> Default for AStar<G, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<G: WeightedGraph + HeuristicGraph> GraphSearcher<G> for AStar<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
+impl<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost> StepSearcher<G, C> for AStar<G, C> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
         self.distances.clear();
         self.parents.clear();
-        self.max_frontier = 1;
+        self.parent_edges.clear();
+        self.closed.clear();
+        self.frontier.clear();
+        self.solution = None;
+        self.overflow = None;
 
-        let mut frontier = BinaryHeap::new();
-        frontier.push(HeapElement::new(root, 0));
+        let heuristic = graph.heuristic(root);
+        match weighted_priority(C::ZERO, heuristic, self.weight) {
+            // a root the goal can't be reached from leaves nothing to search.
+            _ if heuristic == C::MAX => {}
+            Some(key) => self.frontier.push(HeapElement::new(root, key)),
+            None => {
+                self.overflow = Some(CostOverflow {
+                    from: root,
+                    to: root,
+                });
+            }
+        }
 
-        self.distances.insert(root, 0);
+        self.distances.insert(root, C::ZERO);
+    }
 
-        while let Some(HeapElement { node, .. }) = frontier.pop() {
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node, C>> {
+        let (node, key) = loop {
+            let HeapElement { node, cost } = self.frontier.pop()?;
             if graph.is_goal(node) {
+                observer.on_goal(node);
                 self.solution = Some(node);
-                return Some(node);
+                self.frontier.clear();
+                return None;
             }
-            let cost_to_node = self.distances[&node];
-            for child in graph.children(node) {
-                let cost_to_child = cost_to_node + graph.edge_weight(node, child);
-                if cost_to_child < self.distances.get(&child).copied().unwrap_or(i64::MAX) {
-                    self.parents.insert(child, node);
-                    self.distances.insert(child, cost_to_child);
-                    frontier.push(HeapElement::new(
-                        child,
-                        cost_to_child + graph.heuristic(child),
-                    ));
+            // skip entries left behind when a node was reached more cheaply.
+            if !self.closed.insert(node) {
+                continue;
+            }
+            if self.depth_limit.expands_from(graph, node, &self.parents) {
+                break (node, cost);
+            }
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        let cost_to_node = self.distances[&node];
+        graph.for_each_weighted_successor(node, |edge, child, weight| {
+            if self.overflow.is_some() {
+                return;
+            }
+            observer.on_generate(node, child);
+            let heuristic = graph.heuristic(child);
+            if heuristic == C::MAX {
+                observer.on_prune(child);
+                return;
+            }
+            let priced = cost_to_node.checked_add(weight).and_then(|cost_to_child| {
+                let key = weighted_priority(cost_to_child, heuristic, self.weight)?;
+                Some((cost_to_child, key))
+            });
+            let Some((cost_to_child, key)) = priced else {
+                self.overflow = Some(CostOverflow {
+                    from: node,
+                    to: child,
+                });
+                return;
+            };
+            if self
+                .distances
+                .get(&child)
+                .is_none_or(|&known| cost_to_child < known)
+            {
+                if self.closed.remove(&child) {
+                    observer.on_reopen(child);
                 }
+                self.parents.insert(child, node);
+                self.parent_edges.insert(child, edge);
+                self.distances.insert(child, cost_to_child);
+                self.frontier.push(HeapElement::new(child, key));
+            } else {
+                observer.on_duplicate(child);
             }
-            self.max_frontier = std::cmp::max(self.max_frontier, frontier.len());
+        });
+        if self.overflow.is_some() {
+            self.frontier.clear();
         }
-        None
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.frontier.len(),
+            f: Some(key),
+        })
     }
 
-    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
-        let mut distances = HashMap::new();
-        let mut frontier = BinaryHeap::new();
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
 
-        distances.insert(root, 0);
-        frontier.push(HeapElement::new(root, 0));
+    fn solution_cost(&self, _graph: &G) -> Option<C> {
+        self.solution.map(|goal| self.distances[&goal])
+    }
 
-        while let Some(HeapElement { node, cost }) = frontier.pop() {
-            if graph.is_goal(node) {
-                return Some(node);
-            }
-            for child in graph.children(node) {
-                let cost_to_child = cost + graph.edge_weight(node, child);
-                if cost_to_child < distances.get(&child).copied().unwrap_or(i64::MAX) {
-                    distances.insert(child, cost_to_child);
-                    frontier.push(HeapElement::new(child, cost_to_child));
-                }
-            }
-        }
-        None
+    fn cost_overflow(&self) -> Option<CostOverflow<G::Node>> {
+        self.overflow
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.distances.len()
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph<C> + HeuristicGraph<C>, C: Cost> GraphSearcher<G, C> for AStar<G, C> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G, C> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    /// Gives `None` on overflow as well; use `try_search` to tell the two
+    /// apart.
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::try_search(graph, root).ok().flatten()
     }
 
     fn is_visited(&self, node: G::Node) -> bool {
@@ -120,20 +494,43 @@ impl<G: WeightedGraph + HeuristicGraph> GraphSearcher<G> for AStar<G> {
         path.reverse();
         Some(path)
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bestfirst::BestFirstSearch;
+    use crate::budget::SearchOutcome;
+    use crate::dijkstra::Dijkstra;
     use crate::examplegraph::{get_example_graph, ExampleNode};
     use crate::graph::Graph;
     use crate::graphsearcher::GraphSearcher;
+    use crate::observer::CountingObserver;
+    use crate::tilepuzzle::{SlidingPuzzle, TileHeuristic};
+
+    #[test]
+    fn stateless_uses_heuristic() {
+        let puzzle = SlidingPuzzle::random(3, 3, 7).with_heuristic(TileHeuristic::Manhattan);
+        let mut counts = CountingObserver::new();
+        let goal = a_star_search(&puzzle, puzzle.start(), &mut counts).unwrap();
+        assert_eq!(goal, AStar::search(&puzzle, puzzle.start()));
+        assert!(puzzle.is_goal(goal.unwrap()));
+
+        let dijkstra = Dijkstra::new().search_tracked(&puzzle, puzzle.start());
+        assert_eq!(dijkstra.goal(), goal);
+        assert!(counts.expanded < dijkstra.stats.expanded);
+    }
 
     #[test]
     fn basic() {
         let graph = get_example_graph();
         let mut searcher = AStar::new();
-        let found = searcher.search_tracked(&graph, graph.root());
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
         assert!(found.is_some());
         assert!(graph.is_goal(found.unwrap()));
         assert_eq!(
@@ -146,4 +543,154 @@ mod tests {
             ]
         );
     }
+
+    /// Jobs as nodes 0..n, where moving from one to a later one costs
+    /// (hours, changeovers). Fewer hours always wins, and changeovers break
+    /// ties.
+    struct Schedule {
+        steps: Vec<(usize, usize, (i64, i64))>,
+        last: usize,
+    }
+
+    impl Graph for Schedule {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.steps
+                .iter()
+                .filter(|s| s.0 == node)
+                .map(|s| s.1)
+                .collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            (0..self.steps.len())
+                .filter(|&i| self.steps[i].0 == node)
+                .collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            self.steps
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.0 == node)
+                .map(|(i, s)| (i, s.1))
+                .collect()
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.last
+        }
+    }
+
+    impl WeightedGraph<(i64, i64)> for Schedule {
+        fn edge_weight(&self, from: Self::Node, to: Self::Node) -> (i64, i64) {
+            let step = self.steps.iter().find(|s| s.0 == from && s.1 == to);
+            step.unwrap().2
+        }
+
+        fn weighted_successors(&self, node: Self::Node) -> Vec<(usize, usize, (i64, i64))> {
+            self.edges(node)
+                .into_iter()
+                .map(|i| (i, self.steps[i].1, self.steps[i].2))
+                .collect()
+        }
+    }
+
+    impl HeuristicGraph<(i64, i64)> for Schedule {
+        fn heuristic(&self, node: Self::Node) -> (i64, i64) {
+            (i64::from(node != self.last), 0)
+        }
+    }
+
+    #[test]
+    fn lexicographic_costs() {
+        let graph = Schedule {
+            steps: vec![
+                (0, 1, (2, 1)),
+                (1, 3, (2, 1)),
+                (0, 2, (1, 0)),
+                (2, 3, (3, 0)),
+                (0, 3, (5, 0)),
+            ],
+            last: 3,
+        };
+        let mut searcher = AStar::new();
+        let result = searcher.search_tracked(&graph, 0);
+        assert_eq!(result.path.unwrap(), &[0, 2, 3]);
+        assert_eq!(result.cost, Some((4, 0)));
+        assert_eq!(graph.path_cost(&[0, 1, 3]), (4, 2));
+        let found = BestFirstSearch::new().search_tracked(&graph, 0).goal();
+        assert_eq!(found, Some(3));
+        let best = AStar::with_weight(2.0)
+            .search_anytime(&graph, 0, 0.5, None, |_| {})
+            .unwrap()
+            .unwrap();
+        assert_eq!(best.cost, (4, 0));
+        assert!(best.bound <= 2.0);
+    }
+
+    #[test]
+    fn anytime_parallel_edges() {
+        // the cheaper of two steps between the same jobs is listed second, so
+        // `edge_weight` would price the path by the dearer one.
+        let graph = Schedule {
+            steps: vec![(0, 1, (5, 0)), (0, 1, (1, 0)), (1, 2, (1, 0))],
+            last: 2,
+        };
+        let mut searcher = AStar::with_weight(2.0);
+        let best = searcher
+            .search_anytime(&graph, 0, 0.5, None, |_| {})
+            .unwrap()
+            .unwrap();
+        assert_eq!(best.cost, (2, 0));
+        assert_eq!(searcher.path_edges().unwrap(), &[1, 2]);
+    }
+
+    #[test]
+    fn extreme_weights() {
+        // only the tiebreak overflows, on the second step.
+        let graph = Schedule {
+            steps: vec![(0, 1, (1, i64::MAX)), (1, 2, (1, 1))],
+            last: 2,
+        };
+        let result = AStar::new().search_tracked(&graph, 0);
+        let overflow = CostOverflow { from: 1, to: 2 };
+        assert_eq!(result.outcome, SearchOutcome::Overflow(overflow));
+        assert!(result.path.is_none());
+        assert_eq!(AStar::search(&graph, 0), None);
+        assert_eq!(AStar::try_search(&graph, 0), Err(overflow));
+    }
+
+    #[test]
+    fn weighted() {
+        let graph = get_example_graph();
+        let mut searcher = AStar::with_weight(2.5);
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
+        assert!(graph.is_goal(found.unwrap()));
+        assert_eq!(graph.path_cost(&searcher.path().unwrap()), 3);
+    }
+
+    #[test]
+    fn anytime() {
+        let graph = get_example_graph();
+        let mut searcher = AStar::with_weight(3.0);
+        let mut reported = Vec::new();
+        let best = searcher
+            .search_anytime(&graph, graph.root(), 0.5, None, |s| {
+                reported.push(s.clone());
+            })
+            .unwrap()
+            .unwrap();
+        assert!(graph.is_goal(best.goal));
+        assert_eq!(best.cost, 3);
+        assert_eq!(reported.last(), Some(&best));
+        assert!(reported.windows(2).all(|w| w[1].cost < w[0].cost));
+        assert_eq!(searcher.path().unwrap(), best.path);
+    }
 }
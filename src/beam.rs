@@ -0,0 +1,425 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::budget::DepthLimit;
+use crate::graph::{HeuristicGraph, WeightedGraph};
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+pub const DEFAULT_BEAM_WIDTH: usize = 64;
+
+/// How a beam search ranks nodes: the edges it expands through, and what each
+/// adds to a node's g.
+pub trait BeamRanking<G: HeuristicGraph> {
+    /// Calls `f` with each successor of `node` and the cost of reaching it.
+    fn for_each_successor(graph: &G, node: G::Node, f: impl FnMut(G::Edge, G::Node, i64));
+}
+
+/// Ranks nodes by their heuristic alone.
+#[derive(Debug)]
+pub struct ByHeuristic;
+
+impl<G: HeuristicGraph> BeamRanking<G> for ByHeuristic {
+    fn for_each_successor(graph: &G, node: G::Node, mut f: impl FnMut(G::Edge, G::Node, i64)) {
+        graph.for_each_successor(node, |edge, child| f(edge, child, 0));
+    }
+}
+
+/// Ranks nodes by g + h, with g the weight of the edges actually taken.
+#[derive(Debug)]
+pub struct ByPathCost;
+
+impl<G: WeightedGraph + HeuristicGraph> BeamRanking<G> for ByPathCost {
+    fn for_each_successor(graph: &G, node: G::Node, f: impl FnMut(G::Edge, G::Node, i64)) {
+        graph.for_each_weighted_successor(node, f);
+    }
+}
+
+/// Breadth-first search that keeps only the best `width` nodes of each level.
+///
+/// Nodes are ranked by their heuristic, or by g + h for searchers built with
+/// `with_path_cost`. The search is incomplete: if the beam runs dry it can
+/// optionally restart with a wider beam.
+pub struct BeamSearch<G: HeuristicGraph, R: BeamRanking<G> = ByHeuristic> {
+    width: usize,
+    initial_width: usize,
+    widening: Option<(usize, usize)>,
+    ranking: PhantomData<R>,
+    visited: HashSet<G::Node>,
+    parents: HashMap<G::Node, G::Node>,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    costs: HashMap<G::Node, i64>,
+    /// the root while the search is running.
+    root: Option<G::Node>,
+    /// the rest of the level being expanded with their scores, in reverse
+    /// order.
+    level: Vec<(i64, G::Node)>,
+    /// the depth of the level being expanded.
+    depth: usize,
+    candidates: Vec<(i64, G::Node)>,
+    pruned: bool,
+    restarts: usize,
+    solution: Option<G::Node>,
+    depth_limit: DepthLimit,
+}
+
+impl<G: HeuristicGraph, R: BeamRanking<G>> Debug for BeamSearch<G, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BeamSearch")
+    }
+}
+
+impl<G: HeuristicGraph> BeamSearch<G> {
+    /// Creates a beam search that ranks nodes by their heuristic alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    #[must_use]
+    pub fn new(width: usize) -> Self {
+        Self::ranked(width)
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> BeamSearch<G, ByPathCost> {
+    /// Creates a beam search that ranks nodes by g + h.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    #[must_use]
+    pub fn with_path_cost(width: usize) -> Self {
+        Self::ranked(width)
+    }
+}
+
+impl<G: HeuristicGraph, R: BeamRanking<G>> BeamSearch<G, R> {
+    fn ranked(width: usize) -> Self {
+        assert!(width > 0, "beam width must be positive");
+        Self {
+            width,
+            initial_width: width,
+            widening: None,
+            ranking: PhantomData,
+            visited: HashSet::new(),
+            parents: HashMap::new(),
+            parent_edges: HashMap::new(),
+            costs: HashMap::new(),
+            root: None,
+            level: Vec::new(),
+            depth: 0,
+            candidates: Vec::new(),
+            pruned: false,
+            restarts: 0,
+            solution: None,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    /// When the beam runs dry after pruning, restart with the width multiplied
+    /// by `factor`, for as long as the width stays within `max_width`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is less than 2.
+    #[must_use]
+    pub fn with_restarts(mut self, factor: usize, max_width: usize) -> Self {
+        assert!(factor >= 2, "beam widening factor must be at least 2");
+        self.widening = Some((factor, max_width));
+        self
+    }
+
+    /// The beam width used by the last run of the most recent search.
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Starts a single beam of the current width.
+    fn begin_beam(&mut self, graph: &G, root: G::Node) {
+        self.visited.clear();
+        self.parents.clear();
+        self.parent_edges.clear();
+        self.costs.clear();
+        self.candidates.clear();
+        self.pruned = false;
+
+        self.visited.insert(root);
+        self.costs.insert(root, 0);
+        self.level = vec![(graph.heuristic(root), root)];
+        self.depth = 0;
+    }
+
+    /// Keeps the best candidates of the level just expanded as the next one.
+    fn finish_level<O: SearchObserver<G>>(&mut self, observer: &mut O) {
+        let mut candidates = std::mem::take(&mut self.candidates);
+        if candidates.len() > self.width {
+            self.pruned = true;
+            candidates.sort_by_key(|&(score, _)| score);
+            for &(_, node) in &candidates[self.width..] {
+                observer.on_prune(node);
+            }
+            candidates.truncate(self.width);
+        }
+        candidates.reverse();
+        self.level = candidates;
+        self.depth += 1;
+    }
+}
+
+impl<G: HeuristicGraph, R: BeamRanking<G>> StepSearcher<G> for BeamSearch<G, R> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.width = self.initial_width;
+        self.restarts = 0;
+        self.solution = None;
+        self.root = Some(root);
+        self.begin_beam(graph, root);
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        loop {
+            if let Some((score, node)) = self.level.pop() {
+                // only the root can be a goal here, the rest are caught as
+                // they are generated.
+                if graph.is_goal(node) {
+                    observer.on_goal(node);
+                    self.solution = Some(node);
+                    self.root = None;
+                    return None;
+                }
+                if !self.depth_limit.expands(graph, node, self.depth) {
+                    observer.on_prune(node);
+                    if self.level.is_empty() {
+                        self.finish_level(observer);
+                    }
+                    continue;
+                }
+                observer.on_expand(node);
+                let cost_to_node = self.costs[&node];
+                R::for_each_successor(graph, node, |edge, child, weight| {
+                    // the rest of the children aren't generated once a goal is.
+                    if self.solution.is_some() {
+                        return;
+                    }
+                    observer.on_generate(node, child);
+                    if !self.visited.insert(child) {
+                        observer.on_duplicate(child);
+                        return;
+                    }
+                    self.parents.insert(child, node);
+                    self.parent_edges.insert(child, edge);
+                    if graph.is_goal(child) {
+                        // this is the last expansion.
+                        observer.on_goal(child);
+                        self.solution = Some(child);
+                        self.root = None;
+                        self.level.clear();
+                        self.candidates.clear();
+                        return;
+                    }
+                    let cost_to_child = cost_to_node.saturating_add(weight);
+                    self.costs.insert(child, cost_to_child);
+                    let f = cost_to_child.saturating_add(graph.heuristic(child));
+                    if f == i64::MAX {
+                        // no goal is reachable from here.
+                        observer.on_prune(child);
+                        return;
+                    }
+                    self.candidates.push((f, child));
+                });
+                let frontier = self.level.len() + self.candidates.len();
+                if self.level.is_empty() && self.root.is_some() {
+                    self.finish_level(observer);
+                }
+                return Some(SearchStep {
+                    node,
+                    children: Vec::new(),
+                    frontier,
+                    f: Some(score),
+                });
+            }
+            // the beam ran dry.
+            let root = self.root?;
+            match self.widening {
+                Some((factor, max_width)) if self.pruned && self.width * factor <= max_width => {
+                    self.width *= factor;
+                    self.restarts += 1;
+                    self.begin_beam(graph, root);
+                }
+                _ => {
+                    self.root = None;
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: HeuristicGraph, R: BeamRanking<G>> GraphSearcher<G> for BeamSearch<G, R> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::ranked(DEFAULT_BEAM_WIDTH)
+            .search_tracked(graph, root)
+            .goal()
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.visited.contains(&node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        let solution = self.solution?;
+        let mut path = Vec::new();
+        let mut n = solution;
+        path.push(n);
+        while let Some(&parent) = self.parents.get(&n) {
+            path.push(parent);
+            n = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+
+    /// A graph with parallel edges of different weights, and no heuristic.
+    struct MultiGraph {
+        edges: Vec<(usize, usize, i64)>,
+        goal: usize,
+    }
+
+    impl Graph for MultiGraph {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.successors(node).into_iter().map(|(_, n)| n).collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            self.successors(node).into_iter().map(|(e, _)| e).collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            (0..self.edges.len())
+                .filter(|&i| self.edges[i].0 == node)
+                .map(|i| (i, self.edges[i].1))
+                .collect()
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.goal
+        }
+    }
+
+    impl WeightedGraph for MultiGraph {
+        fn edge_weight(&self, from: Self::Node, to: Self::Node) -> i64 {
+            self.edges
+                .iter()
+                .filter(|e| e.0 == from && e.1 == to)
+                .map(|e| e.2)
+                .min()
+                .unwrap()
+        }
+
+        fn weighted_successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node, i64)> {
+            self.successors(node)
+                .into_iter()
+                .map(|(e, n)| (e, n, self.edges[e].2))
+                .collect()
+        }
+    }
+
+    impl HeuristicGraph for MultiGraph {
+        fn heuristic(&self, _node: Self::Node) -> i64 {
+            0
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let graph = get_example_graph();
+        let mut searcher = BeamSearch::with_path_cost(2);
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
+        assert!(graph.is_goal(found.unwrap()));
+        assert_eq!(
+            searcher.path().unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(3),
+                ExampleNode::new(6),
+                ExampleNode::new(7)
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_edges() {
+        // 1 is first reached by its dearer edge, so it costs 10 rather than
+        // the 1 that `edge_weight` would give.
+        let graph = MultiGraph {
+            edges: vec![(0, 1, 10), (0, 1, 1), (0, 2, 5), (1, 3, 1), (2, 3, 1)],
+            goal: 3,
+        };
+        let mut searcher = BeamSearch::with_path_cost(1);
+        let result = searcher.search_tracked(&graph, 0);
+        assert_eq!(result.path.unwrap(), &[0, 2, 3]);
+        assert_eq!(result.edges.unwrap(), &[2, 4]);
+    }
+
+    #[test]
+    fn restarts() {
+        let graph = get_example_graph();
+        // a beam of one follows 8 -> 3 -> 1 and runs dry.
+        let mut narrow = BeamSearch::new(1);
+        assert!(narrow.search_tracked(&graph, graph.root()).goal().is_none());
+        assert!(narrow.path().is_none());
+
+        let mut widening = BeamSearch::new(1).with_restarts(2, 8);
+        let result = widening.search_tracked(&graph, graph.root());
+        assert!(graph.is_goal(result.goal().unwrap()));
+        assert_eq!(widening.restarts(), 1);
+        assert_eq!(widening.width(), 2);
+        assert!(result.stats.max_frontier <= 4);
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt::{Debug, Display};
+
+use crate::budget::DepthLimit;
+use crate::cost::CostOverflow;
+use crate::graph::WeightedGraph;
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+/// A negative-weight cycle reachable from the root, listed in traversal order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NegativeCycle<N> {
+    pub nodes: Vec<N>,
+}
+
+impl<N: Display> Display for NegativeCycle<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "negative cycle: ")?;
+        for node in &self.nodes {
+            write!(f, "{node} -> ")?;
+        }
+        self.nodes
+            .first()
+            .map_or(Ok(()), |first| write!(f, "{first}"))
+    }
+}
+
+impl<N: Display + Debug> Error for NegativeCycle<N> {}
+
+/// Why shortest paths couldn't be computed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShortestPathError<N> {
+    NegativeCycle(NegativeCycle<N>),
+    Overflow(CostOverflow<N>),
+}
+
+impl<N: Display> Display for ShortestPathError<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NegativeCycle(cycle) => write!(f, "{cycle}"),
+            Self::Overflow(overflow) => write!(f, "{overflow}"),
+        }
+    }
+}
+
+impl<N: Display + Debug> Error for ShortestPathError<N> {}
+
+/// Single-source shortest paths with possibly negative edge weights, using
+/// SPFA (queue-based Bellman-Ford) relaxation.
+///
+/// The whole reachable graph is explored, as a negative edge may still
+/// improve the route to a goal after it is first reached.
+pub struct BellmanFord<G: WeightedGraph> {
+    distances: HashMap<G::Node, i64>,
+    parents: HashMap<G::Node, G::Node>,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    /// number of edges on the current best path to each node.
+    lengths: HashMap<G::Node, usize>,
+    queue: VecDeque<G::Node>,
+    queued: HashSet<G::Node>,
+    solution: Option<G::Node>,
+    negative_cycle: Option<NegativeCycle<G::Node>>,
+    /// the edge whose cost overflowed, ending the search.
+    overflow: Option<CostOverflow<G::Node>>,
+    depth_limit: DepthLimit,
+}
+
+impl<G: WeightedGraph> Debug for BellmanFord<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BellmanFord")
+    }
+}
+
+impl<G: WeightedGraph> BellmanFord<G> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            distances: HashMap::new(),
+            parents: HashMap::new(),
+            parent_edges: HashMap::new(),
+            lengths: HashMap::new(),
+            queue: VecDeque::new(),
+            queued: HashSet::new(),
+            solution: None,
+            negative_cycle: None,
+            overflow: None,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    pub const fn distances(&self) -> &HashMap<G::Node, i64> {
+        &self.distances
+    }
+
+    /// The negative cycle found by the most recent search, if any.
+    pub const fn negative_cycle(&self) -> Option<&NegativeCycle<G::Node>> {
+        self.negative_cycle.as_ref()
+    }
+
+    /// Computes shortest distances to every node reachable from `root`, and
+    /// returns the cheapest goal node reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending cycle if a negative cycle is reachable from
+    /// `root`, or the offending edge if the cost of a route overflows.
+    pub fn shortest_paths(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+    ) -> Result<Option<G::Node>, ShortestPathError<G::Node>> {
+        self.search_observed(graph, root, &mut ());
+        if let Some(overflow) = self.overflow {
+            return Err(ShortestPathError::Overflow(overflow));
+        }
+        self.negative_cycle
+            .clone()
+            .map_or(Ok(self.solution), |cycle| {
+                Err(ShortestPathError::NegativeCycle(cycle))
+            })
+    }
+
+    /// Follows parent pointers from `start`, returning the cycle they lead into.
+    fn parent_cycle(&self, start: G::Node) -> Option<NegativeCycle<G::Node>> {
+        let mut seen = HashSet::new();
+        let mut n = start;
+        while seen.insert(n) {
+            n = *self.parents.get(&n)?;
+        }
+        let mut nodes = vec![n];
+        let mut m = self.parents[&n];
+        while m != n {
+            nodes.push(m);
+            m = self.parents[&m];
+        }
+        nodes.reverse();
+        Some(NegativeCycle { nodes })
+    }
+
+    #[must_use]
+    pub fn path_to(&self, node: G::Node) -> Option<Vec<G::Node>> {
+        if self.negative_cycle.is_some()
+            || self.overflow.is_some()
+            || !self.distances.contains_key(&node)
+        {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut n = node;
+        path.push(n);
+        while let Some(&parent) = self.parents.get(&n) {
+            path.push(parent);
+            n = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+impl<G: WeightedGraph> Default for BellmanFord<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Each step relaxes the edges out of one queued node. Nodes may be expanded
+/// several times, and the cheapest goal is only known once the queue is empty.
+impl<G: WeightedGraph> StepSearcher<G> for BellmanFord<G> {
+    fn start(&mut self, _graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.distances.clear();
+        self.parents.clear();
+        self.parent_edges.clear();
+        self.lengths.clear();
+        self.queue.clear();
+        self.queued.clear();
+        self.solution = None;
+        self.negative_cycle = None;
+        self.overflow = None;
+
+        self.distances.insert(root, 0);
+        self.lengths.insert(root, 0);
+        self.queue.push_back(root);
+        self.queued.insert(root);
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        let node = loop {
+            let Some(node) = self.queue.pop_front() else {
+                if self.negative_cycle.is_none()
+                    && self.overflow.is_none()
+                    && self.solution.is_none()
+                {
+                    self.solution = self
+                        .distances
+                        .iter()
+                        .filter(|(&n, _)| graph.is_goal(n))
+                        .min_by_key(|(_, &d)| d)
+                        .map(|(&n, _)| n);
+                    if let Some(goal) = self.solution {
+                        observer.on_goal(goal);
+                    }
+                }
+                return None;
+            };
+            self.queued.remove(&node);
+            if self.depth_limit.expands(graph, node, self.lengths[&node]) {
+                break node;
+            }
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        let cost_to_node = self.distances[&node];
+        let length_to_node = self.lengths[&node];
+        let successors = graph.weighted_successors(node);
+        for &(edge, child, weight) in &successors {
+            observer.on_generate(node, child);
+            let Some(cost_to_child) = cost_to_node.checked_add(weight) else {
+                self.overflow = Some(CostOverflow {
+                    from: node,
+                    to: child,
+                });
+                self.queue.clear();
+                break;
+            };
+            let known = self.distances.get(&child).copied();
+            if known.is_none_or(|known| cost_to_child < known) {
+                // every reached node is queued until it is expanded.
+                if known.is_some() && !self.queued.contains(&child) {
+                    observer.on_reopen(child);
+                }
+                self.parents.insert(child, node);
+                self.parent_edges.insert(child, edge);
+                self.distances.insert(child, cost_to_child);
+                self.lengths.insert(child, length_to_node + 1);
+                // a shortest path can't use more edges than there are nodes,
+                // so the parent pointers should now contain a cycle.
+                if length_to_node + 1 >= self.distances.len() {
+                    if let Some(cycle) = self.parent_cycle(child) {
+                        self.negative_cycle = Some(cycle);
+                        self.queue.clear();
+                        break;
+                    }
+                }
+                if self.queued.insert(child) {
+                    self.queue.push_back(child);
+                }
+            } else {
+                observer.on_duplicate(child);
+            }
+        }
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.queue.len(),
+            f: None,
+        })
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
+
+    fn solution_cost(&self, _graph: &G) -> Option<i64> {
+        self.solution.map(|goal| self.distances[&goal])
+    }
+
+    fn cost_overflow(&self) -> Option<CostOverflow<G::Node>> {
+        self.overflow
+    }
+
+    fn negative_cycle_node(&self) -> Option<G::Node> {
+        self.negative_cycle
+            .as_ref()
+            .and_then(|cycle| cycle.nodes.first().copied())
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph> GraphSearcher<G> for BellmanFord<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::new().shortest_paths(graph, root).ok().flatten()
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.distances.contains_key(&node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        self.path_to(self.solution?)
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::SearchOutcome;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+    use crate::observer::CountingObserver;
+
+    struct NegativeGraph {
+        edges: Vec<(usize, usize, i64)>,
+        goal: usize,
+    }
+
+    impl Graph for NegativeGraph {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.edges
+                .iter()
+                .filter(|e| e.0 == node)
+                .map(|e| e.1)
+                .collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            (0..self.edges.len())
+                .filter(|&i| self.edges[i].0 == node)
+                .collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            self.edges
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.0 == node)
+                .map(|(i, e)| (i, e.1))
+                .collect()
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.goal
+        }
+    }
+
+    impl WeightedGraph for NegativeGraph {
+        fn edge_weight(&self, from: Self::Node, to: Self::Node) -> i64 {
+            self.edges
+                .iter()
+                .find(|e| e.0 == from && e.1 == to)
+                .unwrap()
+                .2
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let graph = get_example_graph();
+        let mut searcher = BellmanFord::new();
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
+        assert!(graph.is_goal(found.unwrap()));
+        assert_eq!(
+            searcher.path().unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(3),
+                ExampleNode::new(6),
+                ExampleNode::new(7)
+            ]
+        );
+    }
+
+    #[test]
+    fn negative_edges() {
+        let graph = NegativeGraph {
+            edges: vec![(0, 1, 4), (0, 2, 5), (2, 1, -3), (1, 3, 1)],
+            goal: 3,
+        };
+        let mut searcher = BellmanFord::new();
+        assert_eq!(searcher.shortest_paths(&graph, 0), Ok(Some(3)));
+        assert_eq!(searcher.path().unwrap(), &[0, 2, 1, 3]);
+        assert_eq!(searcher.distances()[&3], 3);
+        assert_eq!(graph.path_cost(&searcher.path().unwrap()), 3);
+    }
+
+    #[test]
+    fn reopen() {
+        let graph = NegativeGraph {
+            edges: vec![(0, 1, 4), (0, 2, 5), (2, 1, -3), (1, 3, 1)],
+            goal: 3,
+        };
+        // 1 and 3 are both expanded before the cheaper route through 2
+        // reaches them.
+        let mut counts = CountingObserver::new();
+        let found = BellmanFord::new()
+            .search_observed(&graph, 0, &mut counts)
+            .goal();
+        assert_eq!(found, Some(3));
+        assert_eq!((counts.reopened, counts.goals), (2, 1));
+        assert_eq!(counts.expanded, 6);
+    }
+
+    #[test]
+    fn negative_cycle() {
+        let graph = NegativeGraph {
+            edges: vec![(0, 1, 1), (1, 2, 1), (2, 1, -3), (2, 3, 1)],
+            goal: 3,
+        };
+        let mut searcher = BellmanFord::new();
+        let Err(ShortestPathError::NegativeCycle(cycle)) = searcher.shortest_paths(&graph, 0)
+        else {
+            panic!("expected a negative cycle");
+        };
+        let mut nodes = cycle.nodes.clone();
+        nodes.sort_unstable();
+        assert_eq!(nodes, &[1, 2]);
+        assert_eq!(searcher.negative_cycle(), Some(&cycle));
+        let result = searcher.search_tracked(&graph, 0);
+        assert_eq!(result.outcome, SearchOutcome::NegativeCycle(cycle.nodes[0]));
+        assert!(result.goal().is_none());
+        assert!(searcher.path().is_none());
+    }
+
+    #[test]
+    fn extreme_weights() {
+        let graph = NegativeGraph {
+            edges: vec![(0, 1, i64::MAX - 1), (1, 2, 1)],
+            goal: 2,
+        };
+        let result = BellmanFord::new().search_tracked(&graph, 0);
+        assert_eq!(result.cost, Some(i64::MAX));
+
+        let graph = NegativeGraph {
+            edges: vec![(0, 1, i64::MAX - 1), (1, 2, 5)],
+            goal: 2,
+        };
+        let mut searcher = BellmanFord::new();
+        let result = searcher.search_tracked(&graph, 0);
+        let overflow = CostOverflow { from: 1, to: 2 };
+        assert_eq!(result.outcome, SearchOutcome::Overflow(overflow));
+        assert!(result.path.is_none());
+        assert_eq!(
+            searcher.shortest_paths(&graph, 0),
+            Err(ShortestPathError::Overflow(overflow))
+        );
+        assert_eq!(BellmanFord::search(&graph, 0), None);
+    }
+}
@@ -1,74 +1,134 @@
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::Debug;
 
+use crate::budget::DepthLimit;
+use crate::cost::Cost;
 use crate::graph::HeuristicGraph;
-use crate::graphsearcher::GraphSearcher;
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
 
 use crate::heapelement::HeapElement;
 
-pub struct BestFirstSearch<G: HeuristicGraph> {
+pub struct BestFirstSearch<G: HeuristicGraph<C>, C: Cost = i64> {
     visited: HashSet<G::Node>,
     parents: HashMap<G::Node, G::Node>,
-    max_frontier: usize,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    frontier: BinaryHeap<HeapElement<G::Node, C>>,
     solution: Option<G::Node>,
+    depth_limit: DepthLimit,
 }
 
-impl<G: HeuristicGraph> Debug for BestFirstSearch<G> {
+impl<G: HeuristicGraph<C>, C: Cost> Debug for BestFirstSearch<G, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "BestFirstSearch")
     }
 }
 
-impl<G: HeuristicGraph> BestFirstSearch<G> {
+impl<G: HeuristicGraph<C>, C: Cost> BestFirstSearch<G, C> {
     #[must_use]
     pub fn new() -> Self {
         Self {
             visited: HashSet::new(),
             parents: HashMap::new(),
-            max_frontier: 1,
+            parent_edges: HashMap::new(),
+            frontier: BinaryHeap::new(),
             solution: None,
+            depth_limit: DepthLimit::new(),
         }
     }
-
-    pub const fn max_frontier(&self) -> usize {
-        self.max_frontier
-    }
 }
 
-impl<G: HeuristicGraph> Default for BestFirstSearch<G> {
+impl<G: HeuristicGraph<C>, C: Cost> Default for BestFirstSearch<G, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<G: HeuristicGraph> GraphSearcher<G> for BestFirstSearch<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
+impl<G: HeuristicGraph<C>, C: Cost> StepSearcher<G, C> for BestFirstSearch<G, C> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
         self.visited.clear();
         self.parents.clear();
-        self.max_frontier = 1;
-        let mut frontier = BinaryHeap::new();
+        self.parent_edges.clear();
+        self.frontier.clear();
+        self.solution = None;
 
         self.visited.insert(root);
-        frontier.push(HeapElement::new(root, graph.heuristic(root)));
-
-        while let Some(best_next_node) = frontier.pop() {
-            let best_next_node = *best_next_node.node();
-            for child in graph.children(best_next_node) {
-                if !self.is_visited(child) {
-                    self.parents.insert(child, best_next_node);
-
-                    if graph.is_goal(child) {
-                        self.solution = Some(child);
-                        return Some(child);
-                    }
+        self.frontier
+            .push(HeapElement::new(root, graph.heuristic(root)));
+    }
 
-                    self.visited.insert(child);
-                    frontier.push(HeapElement::new(child, graph.heuristic(child)));
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node, C>> {
+        let (node, cost) = loop {
+            let HeapElement { node, cost } = self.frontier.pop()?;
+            // only the root can be a goal here, the rest are caught as they
+            // are generated.
+            if graph.is_goal(node) {
+                observer.on_goal(node);
+                self.solution = Some(node);
+                return None;
+            }
+            if self.depth_limit.expands_from(graph, node, &self.parents) {
+                break (node, cost);
+            }
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        graph.for_each_successor(node, |edge, child| {
+            // the rest of the children aren't generated once a goal is.
+            if self.solution.is_some() {
+                return;
+            }
+            observer.on_generate(node, child);
+            if self.is_visited(child) {
+                observer.on_duplicate(child);
+            } else {
+                self.parents.insert(child, node);
+                self.parent_edges.insert(child, edge);
+
+                if graph.is_goal(child) {
+                    // this is the last expansion.
+                    observer.on_goal(child);
+                    self.solution = Some(child);
+                    self.frontier.clear();
+                    return;
                 }
+
+                self.visited.insert(child);
+                self.frontier
+                    .push(HeapElement::new(child, graph.heuristic(child)));
             }
-            self.max_frontier = std::cmp::max(self.max_frontier, frontier.len());
-        }
-        None
+        });
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.frontier.len(),
+            f: Some(cost),
+        })
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: HeuristicGraph<C>, C: Cost> GraphSearcher<G, C> for BestFirstSearch<G, C> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G, C> {
+        self.search_observed(graph, root, &mut ())
     }
 
     fn search(graph: &G, root: G::Node) -> Option<G::Node> {
@@ -76,28 +136,28 @@ impl<G: HeuristicGraph> GraphSearcher<G> for BestFirstSearch<G> {
         let mut frontier = BinaryHeap::new();
 
         visited.insert(root);
-        frontier.push(HeapElement::new(root, 0));
+        frontier.push(HeapElement::new(root, C::ZERO));
 
         while let Some(best_next_node) = frontier.pop() {
             let best_next_node = *best_next_node.node();
-            for child in graph.children(best_next_node) {
-                if !visited.contains(&child) {
+            let mut found = None;
+            graph.for_each_child(best_next_node, |child| {
+                if found.is_none() && visited.insert(child) {
                     if graph.is_goal(child) {
-                        return Some(child);
+                        found = Some(child);
+                        return;
                     }
 
-                    visited.insert(child);
                     frontier.push(HeapElement::new(child, graph.heuristic(child)));
                 }
+            });
+            if found.is_some() {
+                return found;
             }
         }
         None
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.visited.len()
-    }
-
     fn is_visited(&self, node: G::Node) -> bool {
         self.visited.contains(&node)
     }
@@ -114,6 +174,11 @@ impl<G: HeuristicGraph> GraphSearcher<G> for BestFirstSearch<G> {
         path.reverse();
         Some(path)
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
 }
 
 #[cfg(test)]
@@ -126,7 +191,7 @@ mod tests {
     fn basic() {
         let graph = get_example_graph();
         let mut searcher = BestFirstSearch::new();
-        let solution = searcher.search_tracked(&graph, graph.root());
+        let solution = searcher.search_tracked(&graph, graph.root()).goal();
         assert!(graph.is_goal(solution.unwrap()));
     }
 }
@@ -1,12 +1,23 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
 
-use crate::{graph::Graph, graphsearcher::GraphSearcher};
+use crate::{
+    budget::DepthLimit,
+    graph::{Graph, ReversibleGraph},
+    graphsearcher::{GraphSearcher, SearchStep, StepSearcher},
+    observer::SearchObserver,
+    searchresult::SearchResult,
+};
 
 pub struct BFS<G: Graph> {
     visited: HashSet<G::Node>,
     parents: HashMap<G::Node, G::Node>,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    /// the frontier, with the depth of each node.
+    queue: VecDeque<(G::Node, usize)>,
     solution: Option<G::Node>,
-    max_frontier: usize,
+    depth_limit: DepthLimit,
 }
 
 impl<G: Graph> BFS<G> {
@@ -15,16 +26,13 @@ impl<G: Graph> BFS<G> {
         Self {
             visited: HashSet::new(),
             parents: HashMap::new(),
+            parent_edges: HashMap::new(),
+            queue: VecDeque::new(),
             solution: None,
-            max_frontier: 1,
+            depth_limit: DepthLimit::new(),
         }
     }
 
-    #[must_use]
-    pub const fn max_frontier(&self) -> usize {
-        self.max_frontier
-    }
-
     fn mark_visited(&mut self, node: G::Node) {
         self.visited.insert(node);
     }
@@ -36,26 +44,72 @@ impl<G: Graph> Default for BFS<G> {
     }
 }
 
-impl<G: Graph> GraphSearcher<G> for BFS<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
-        let mut queue = VecDeque::new();
+impl<G: Graph> StepSearcher<G> for BFS<G> {
+    fn start(&mut self, _graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.visited.clear();
+        self.parents.clear();
+        self.parent_edges.clear();
+        self.queue.clear();
+        self.solution = None;
         self.mark_visited(root);
-        queue.push_back(root);
-        while let Some(node) = queue.pop_front() {
+        self.queue.push_back((root, 0));
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        let (node, depth) = loop {
+            let (node, depth) = self.queue.pop_front()?;
             if graph.is_goal(node) {
+                observer.on_goal(node);
                 self.solution = Some(node);
-                return Some(node);
+                self.queue.clear();
+                return None;
+            }
+            if self.depth_limit.expands(graph, node, depth) {
+                break (node, depth);
             }
-            for neighbor in graph.children(node) {
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        graph.for_each_successor(node, |edge, neighbor| {
+            observer.on_generate(node, neighbor);
+            if self.is_visited(neighbor) {
+                observer.on_duplicate(neighbor);
+            } else {
                 self.parents.insert(neighbor, node);
-                if !self.is_visited(neighbor) {
-                    self.mark_visited(neighbor);
-                    queue.push_back(neighbor);
-                }
+                self.parent_edges.insert(neighbor, edge);
+                self.mark_visited(neighbor);
+                self.queue.push_back((neighbor, depth + 1));
             }
-            self.max_frontier = std::cmp::max(self.max_frontier, queue.len());
-        }
-        None
+        });
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.queue.len(),
+            f: None,
+        })
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: Graph> GraphSearcher<G> for BFS<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
     }
 
     fn search(graph: &G, root: G::Node) -> Option<G::Node> {
@@ -67,20 +121,15 @@ impl<G: Graph> GraphSearcher<G> for BFS<G> {
             if graph.is_goal(node) {
                 return Some(node);
             }
-            for neighbor in graph.children(node) {
-                if !visited.contains(&neighbor) {
-                    visited.insert(neighbor);
+            graph.for_each_child(node, |neighbor| {
+                if visited.insert(neighbor) {
                     queue.push_back(neighbor);
                 }
-            }
+            });
         }
         None
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.visited.len()
-    }
-
     fn is_visited(&self, node: G::Node) -> bool {
         self.visited.contains(&node)
     }
@@ -97,19 +146,353 @@ impl<G: Graph> GraphSearcher<G> for BFS<G> {
         path.reverse();
         Some(path)
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
+}
+
+/// Breadth-first search that grows one frontier forwards from the root and
+/// another backwards from an explicit goal node, stopping when they meet.
+///
+/// Any node the forward search reaches that `is_goal` also ends the search,
+/// so a root that is a goal is found at once.
+pub struct BidirectionalBFS<G: ReversibleGraph> {
+    goal: G::Node,
+    forward: HashMap<G::Node, usize>,
+    backward: HashMap<G::Node, usize>,
+    /// the parent of each node reached forwards, and the edge from it.
+    parents: HashMap<G::Node, (G::Node, G::Edge)>,
+    /// the successor of each node reached backwards, and the edge to it.
+    successors: HashMap<G::Node, (G::Node, G::Edge)>,
+    forward_frontier: Vec<G::Node>,
+    backward_frontier: Vec<G::Node>,
+    /// the rest of the layer being expanded, in reverse order.
+    layer: Vec<G::Node>,
+    next_layer: Vec<G::Node>,
+    /// whether the current layer belongs to the backward search.
+    backwards: bool,
+    /// the meeting node with the shortest route found in the current layer.
+    best: Option<(usize, G::Node)>,
+    meeting: Option<G::Node>,
+    /// the goal the path through `meeting` ends at.
+    solution: Option<G::Node>,
+    depth_limit: DepthLimit,
+}
+
+impl<G: ReversibleGraph> BidirectionalBFS<G> {
+    #[must_use]
+    pub fn new(goal: G::Node) -> Self {
+        Self {
+            goal,
+            forward: HashMap::new(),
+            backward: HashMap::new(),
+            parents: HashMap::new(),
+            successors: HashMap::new(),
+            forward_frontier: Vec::new(),
+            backward_frontier: Vec::new(),
+            layer: Vec::new(),
+            next_layer: Vec::new(),
+            backwards: false,
+            best: None,
+            meeting: None,
+            solution: None,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn goal(&self) -> G::Node {
+        self.goal
+    }
+
+    /// The node at which the two frontiers met, if the search succeeded.
+    #[must_use]
+    pub const fn meeting_point(&self) -> Option<G::Node> {
+        self.meeting
+    }
+
+    /// Ends the current layer, making the nodes it reached the frontier of
+    /// its side. The search is over if they include a meeting node or a goal.
+    fn finish_layer<O: SearchObserver<G>>(&mut self, graph: &G, observer: &mut O) {
+        let next = std::mem::take(&mut self.next_layer);
+        if self.backwards {
+            self.backward_frontier = next;
+        } else {
+            self.forward_frontier = next;
+        }
+        if let Some((_, meeting)) = self.best.take() {
+            let goal = if graph.is_goal(meeting) {
+                meeting
+            } else {
+                self.goal
+            };
+            observer.on_goal(goal);
+            self.meeting = Some(meeting);
+            self.solution = Some(goal);
+            self.forward_frontier.clear();
+            self.backward_frontier.clear();
+        }
+    }
+}
+
+/// Reports the neighbours of `node` as generated, and as duplicates if the
+/// same search has reached them already.
+fn observe_neighbors<G: Graph, O: SearchObserver<G>>(
+    node: G::Node,
+    neighbors: &[(G::Edge, G::Node)],
+    depths: &HashMap<G::Node, usize>,
+    observer: &mut O,
+) {
+    for &(_, neighbor) in neighbors {
+        observer.on_generate(node, neighbor);
+        if depths.contains_key(&neighbor) {
+            observer.on_duplicate(neighbor);
+        }
+    }
+}
+
+/// Records depths and links for the newly reached neighbours of `node`, and
+/// keeps track of the meeting node with the shortest combined route if any
+/// of them has been reached by the opposite search or is a goal.
+#[allow(clippy::too_many_arguments)]
+fn expand_node<N: Copy + Eq + Hash, E: Copy>(
+    node: N,
+    neighbors: &[(E, N)],
+    depths: &mut HashMap<N, usize>,
+    links: &mut HashMap<N, (N, E)>,
+    opposite: &HashMap<N, usize>,
+    is_goal: impl Fn(N) -> bool,
+    next: &mut Vec<N>,
+    best: &mut Option<(usize, N)>,
+) {
+    let depth = depths[&node] + 1;
+    for &(edge, neighbor) in neighbors {
+        if let Entry::Vacant(entry) = depths.entry(neighbor) {
+            entry.insert(depth);
+            links.insert(neighbor, (node, edge));
+            next.push(neighbor);
+            let remaining = if is_goal(neighbor) {
+                Some(&0)
+            } else {
+                opposite.get(&neighbor)
+            };
+            if let Some(&remaining) = remaining {
+                let length = depth + remaining;
+                if best.is_none_or(|(best_length, _)| length < best_length) {
+                    *best = Some((length, neighbor));
+                }
+            }
+        }
+    }
+}
+
+impl<G: ReversibleGraph> StepSearcher<G> for BidirectionalBFS<G> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.forward.clear();
+        self.backward.clear();
+        self.parents.clear();
+        self.successors.clear();
+        self.forward_frontier.clear();
+        self.backward_frontier.clear();
+        self.layer.clear();
+        self.next_layer.clear();
+        self.best = None;
+        self.meeting = None;
+        self.solution = None;
+
+        self.forward.insert(root, 0);
+        self.backward.insert(self.goal, 0);
+        if root == self.goal || graph.is_goal(root) {
+            // the first step reports the meeting.
+            self.best = Some((0, root));
+        } else {
+            self.forward_frontier.push(root);
+            self.backward_frontier.push(self.goal);
+        }
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        if self.layer.is_empty() {
+            if self.best.is_some() {
+                self.finish_layer(graph, observer);
+            }
+            if self.meeting.is_some()
+                || self.forward_frontier.is_empty()
+                || self.backward_frontier.is_empty()
+            {
+                return None;
+            }
+            // every route of up to forward + backward depth edges has been
+            // tried, the next layer only finds longer ones.
+            let depth =
+                self.forward[&self.forward_frontier[0]] + self.backward[&self.backward_frontier[0]];
+            if !self.depth_limit.allows(depth + 1) {
+                self.depth_limit.cut();
+                self.forward_frontier.clear();
+                self.backward_frontier.clear();
+                return None;
+            }
+            // always grow the smaller frontier, this is what keeps the search
+            // close to the square root of a unidirectional one.
+            self.backwards = self.forward_frontier.len() > self.backward_frontier.len();
+            self.layer = if self.backwards {
+                std::mem::take(&mut self.backward_frontier)
+            } else {
+                std::mem::take(&mut self.forward_frontier)
+            };
+            self.layer.reverse();
+        }
+
+        let node = self.layer.pop()?;
+        observer.on_expand(node);
+        if self.backwards {
+            let parents = graph.predecessors(node);
+            observe_neighbors(node, &parents, &self.backward, observer);
+            expand_node(
+                node,
+                &parents,
+                &mut self.backward,
+                &mut self.successors,
+                &self.forward,
+                |_| false,
+                &mut self.next_layer,
+                &mut self.best,
+            );
+        } else {
+            let children = graph.successors(node);
+            observe_neighbors(node, &children, &self.forward, observer);
+            expand_node(
+                node,
+                &children,
+                &mut self.forward,
+                &mut self.parents,
+                &self.backward,
+                |n| graph.is_goal(n),
+                &mut self.next_layer,
+                &mut self.best,
+            );
+        }
+        if self.layer.is_empty() {
+            self.finish_layer(graph, observer);
+        }
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.layer.len()
+                + self.next_layer.len()
+                + self.forward_frontier.len()
+                + self.backward_frontier.len(),
+            f: None,
+        })
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: ReversibleGraph> GraphSearcher<G> for BidirectionalBFS<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    /// Without a searcher instance there is no goal node to search backwards
+    /// from, so this falls back to a plain forward breadth-first search.
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        BFS::search(graph, root)
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.forward.contains_key(&node) || self.backward.contains_key(&node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        let meeting = self.meeting?;
+        let mut path = Vec::new();
+        let mut n = meeting;
+        path.push(n);
+        while let Some(&(parent, _)) = self.parents.get(&n) {
+            path.push(parent);
+            n = parent;
+        }
+        path.reverse();
+        n = meeting;
+        while n != self.solution? {
+            let (successor, _) = self.successors[&n];
+            path.push(successor);
+            n = successor;
+        }
+        Some(path)
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let meeting = self.meeting?;
+        let mut edges = Vec::new();
+        let mut n = meeting;
+        while let Some(&(parent, edge)) = self.parents.get(&n) {
+            edges.push(edge);
+            n = parent;
+        }
+        edges.reverse();
+        n = meeting;
+        while n != self.solution? {
+            let (successor, edge) = self.successors[&n];
+            edges.push(edge);
+            n = successor;
+        }
+        Some(edges)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::examplegraph::ExampleNode;
+    use crate::examplegraph::{ExampleEdge, ExampleNode};
     use crate::graphsearcher::GraphSearcher;
-    use crate::{bfs::BFS, examplegraph, graph::Graph};
+    use crate::{
+        bfs::{BidirectionalBFS, BFS},
+        examplegraph,
+        graph::Graph,
+    };
 
     #[test]
     fn basic() {
         let graph = examplegraph::get_example_graph();
         let mut searcher = BFS::new();
-        let found = searcher.search_tracked(&graph, graph.root());
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
+        assert!(found.is_some());
+        assert!(graph.is_goal(found.unwrap()));
+        assert_eq!(
+            searcher.path().unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(3),
+                ExampleNode::new(6),
+                ExampleNode::new(7)
+            ]
+        );
+    }
+
+    #[test]
+    fn bidirectional() {
+        let graph = examplegraph::get_example_graph();
+        let mut searcher = BidirectionalBFS::new(ExampleNode::new(7));
+        let result = searcher.search_tracked(&graph, graph.root());
+        let found = result.goal();
         assert!(found.is_some());
         assert!(graph.is_goal(found.unwrap()));
         assert_eq!(
@@ -121,5 +504,35 @@ mod tests {
                 ExampleNode::new(7)
             ]
         );
+        // the backward half is labelled with the same edges as the forward.
+        let edges = [(8, 3), (3, 6), (6, 7)].map(|(from, to)| ExampleEdge::new(from, to));
+        assert_eq!(result.edges.unwrap(), edges);
+    }
+
+    #[test]
+    fn bidirectional_goal_root() {
+        // the root is a goal, though not the one searched back from.
+        let graph = examplegraph::get_example_graph();
+        let root = ExampleNode::new(7);
+        let mut searcher = BidirectionalBFS::new(ExampleNode::new(13));
+        let result = searcher.search_tracked(&graph, root);
+        assert_eq!(result.goal(), Some(root));
+        assert_eq!(result.path.unwrap(), &[root]);
+        assert_eq!(result.edges.unwrap(), &[]);
+        assert_eq!(
+            BFS::new().search_tracked(&graph, root).path.unwrap(),
+            &[root]
+        );
+    }
+
+    #[test]
+    fn bidirectional_unreachable() {
+        let graph = examplegraph::get_example_graph();
+        let mut searcher = BidirectionalBFS::new(ExampleNode::new(3));
+        assert!(searcher
+            .search_tracked(&graph, ExampleNode::new(10))
+            .goal()
+            .is_none());
+        assert!(searcher.path().is_none());
     }
 }
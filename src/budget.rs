@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cost::CostOverflow;
+use crate::graph::Graph;
+
+/// Limits on how much work a search may do, for
+/// `StepSearcher::search_within`. Every limit is off by default.
+#[derive(Clone, Debug, Default)]
+pub struct SearchBudget {
+    max_expansions: Option<usize>,
+    max_depth: Option<usize>,
+    deadline: Option<Instant>,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl SearchBudget {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the search after `max_expansions` steps.
+    #[must_use]
+    pub const fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = Some(max_expansions);
+        self
+    }
+
+    /// Stops nodes more than `max_depth` edges from the root being expanded.
+    #[must_use]
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline to `limit` from now.
+    #[must_use]
+    pub fn with_time_limit(self, limit: Duration) -> Self {
+        self.with_deadline(Instant::now() + limit)
+    }
+
+    /// Stops the search once `flag` is set, which can be done from another
+    /// thread.
+    #[must_use]
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(flag);
+        self
+    }
+
+    #[must_use]
+    pub const fn max_expansions(&self) -> Option<usize> {
+        self.max_expansions
+    }
+
+    #[must_use]
+    pub const fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    #[must_use]
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The limit that stops a search after `expansions` steps from making
+    /// another, if any. The depth limit is enforced by the searcher instead.
+    #[must_use]
+    pub fn check(&self, expansions: usize) -> Option<BudgetLimit> {
+        if self
+            .cancelled
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+        {
+            Some(BudgetLimit::Cancelled)
+        } else if self.max_expansions.is_some_and(|max| expansions >= max) {
+            Some(BudgetLimit::Expansions)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(BudgetLimit::Time)
+        } else {
+            None
+        }
+    }
+}
+
+/// The limit of a `SearchBudget` that ended a search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BudgetLimit {
+    Expansions,
+    /// Nodes at the depth limit were left unexpanded, so a goal may lie
+    /// beyond it.
+    Depth,
+    Time,
+    Cancelled,
+}
+
+impl Display for BudgetLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Expansions => write!(f, "expansion limit reached"),
+            Self::Depth => write!(f, "depth limit reached"),
+            Self::Time => write!(f, "deadline passed"),
+            Self::Cancelled => write!(f, "search cancelled"),
+        }
+    }
+}
+
+/// How a search within a budget ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchOutcome<N> {
+    Found(N),
+    /// The search space was exhausted without finding a goal.
+    NotFound,
+    /// The budget ran out before the search could finish.
+    Exhausted(BudgetLimit),
+    /// A path cost grew too large for its type, so the search was abandoned
+    /// rather than compare wrong costs.
+    Overflow(CostOverflow<N>),
+    /// A cycle of negative cost was reached, through the given node, so no
+    /// path has a least cost.
+    NegativeCycle(N),
+}
+
+impl<N> SearchOutcome<N> {
+    /// The goal, if one was found.
+    pub fn found(self) -> Option<N> {
+        match self {
+            Self::Found(goal) => Some(goal),
+            Self::NotFound | Self::Exhausted(_) | Self::Overflow(_) | Self::NegativeCycle(_) => {
+                None
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn is_exhausted(&self) -> bool {
+        matches!(self, Self::Exhausted(_))
+    }
+}
+
+/// A searcher's depth limit, and whether it has cut a search short.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DepthLimit {
+    max: Option<usize>,
+    cut_off: bool,
+}
+
+impl DepthLimit {
+    pub(crate) const fn new() -> Self {
+        Self {
+            max: None,
+            cut_off: false,
+        }
+    }
+
+    pub(crate) const fn set(&mut self, max: Option<usize>) {
+        self.max = max;
+    }
+
+    pub(crate) const fn reset(&mut self) {
+        self.cut_off = false;
+    }
+
+    pub(crate) const fn cut_off(self) -> bool {
+        self.cut_off
+    }
+
+    /// Whether nodes at `depth` are within the limit.
+    pub(crate) fn allows(self, depth: usize) -> bool {
+        self.max.is_none_or(|max| depth <= max)
+    }
+
+    /// Whether `node`, at `depth`, may be expanded. If not, and it has
+    /// children, the search has been cut short.
+    pub(crate) fn expands<G: Graph>(&mut self, graph: &G, node: G::Node, depth: usize) -> bool {
+        if self.max.is_none_or(|max| depth < max) {
+            return true;
+        }
+        if !self.cut_off {
+            graph.for_each_child(node, |_| self.cut_off = true);
+        }
+        false
+    }
+
+    /// Like `expands`, with the depth of `node` found by following `parents`
+    /// back to the root. The chain is only walked if there is a limit.
+    pub(crate) fn expands_from<G: Graph>(
+        &mut self,
+        graph: &G,
+        node: G::Node,
+        parents: &HashMap<G::Node, G::Node>,
+    ) -> bool {
+        if self.max.is_none() {
+            return true;
+        }
+        let mut depth = 0;
+        let mut n = node;
+        while let Some(&parent) = parents.get(&n) {
+            depth += 1;
+            n = parent;
+        }
+        self.expands(graph, node, depth)
+    }
+
+    /// Records that the search has been cut short.
+    pub(crate) const fn cut(&mut self) {
+        self.cut_off = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::beam::BeamSearch;
+    use crate::bellmanford::BellmanFord;
+    use crate::bestfirst::BestFirstSearch;
+    use crate::bfs::{BidirectionalBFS, BFS};
+    use crate::dfs::{IterDeepening, DFS};
+    use crate::dijkstra::Dijkstra;
+    use crate::examplegraph::{get_example_graph, ExampleGraph, ExampleNode};
+    use crate::graphsearcher::StepSearcher;
+    use crate::idastar::IdaStar;
+    use crate::rbfs::Rbfs;
+    use crate::smastar::SmaStar;
+
+    fn within<S: StepSearcher<ExampleGraph>>(
+        mut searcher: S,
+        budget: &SearchBudget,
+    ) -> SearchOutcome<ExampleNode> {
+        let graph = get_example_graph();
+        searcher.search_within(&graph, graph.root(), budget).outcome
+    }
+
+    #[test]
+    fn limits() {
+        let goal = ExampleNode::new(7);
+        assert_eq!(
+            within(BFS::new(), &SearchBudget::new()),
+            SearchOutcome::Found(goal)
+        );
+        assert_eq!(
+            within(BFS::new(), &SearchBudget::new().with_max_expansions(2)),
+            SearchOutcome::Exhausted(BudgetLimit::Expansions)
+        );
+        assert_eq!(
+            within(
+                BFS::new(),
+                &SearchBudget::new().with_time_limit(Duration::ZERO)
+            ),
+            SearchOutcome::Exhausted(BudgetLimit::Time)
+        );
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let budget = SearchBudget::new().with_cancel_flag(Arc::clone(&flag));
+        assert_eq!(within(BFS::new(), &budget), SearchOutcome::Found(goal));
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(
+            within(BFS::new(), &budget),
+            SearchOutcome::Exhausted(BudgetLimit::Cancelled)
+        );
+
+        // nothing below 10 is cut off, so the search space is exhausted.
+        let graph = get_example_graph();
+        let budget = SearchBudget::new().with_max_depth(2);
+        assert_eq!(
+            BFS::new()
+                .search_within(&graph, ExampleNode::new(10), &budget)
+                .outcome,
+            SearchOutcome::NotFound
+        );
+    }
+
+    /// The goal is three edges from the root.
+    fn check_depth<S: StepSearcher<ExampleGraph>>(mut searcher: S) {
+        let graph = get_example_graph();
+        let shallow = SearchBudget::new().with_max_depth(2);
+        let outcome = searcher
+            .search_within(&graph, graph.root(), &shallow)
+            .outcome;
+        assert_eq!(outcome, SearchOutcome::Exhausted(BudgetLimit::Depth));
+        let deep = SearchBudget::new().with_max_depth(3);
+        let outcome = searcher.search_within(&graph, graph.root(), &deep).outcome;
+        assert_eq!(outcome, SearchOutcome::Found(ExampleNode::new(7)));
+        // the limit only applies to searches within the budget.
+        assert!(searcher
+            .search_tracked(&graph, graph.root())
+            .goal()
+            .is_some());
+    }
+
+    #[test]
+    fn depth() {
+        check_depth(BFS::new());
+        check_depth(BidirectionalBFS::new(ExampleNode::new(7)));
+        check_depth(DFS::new());
+        check_depth(IterDeepening::new());
+        check_depth(BestFirstSearch::new());
+        check_depth(AStar::new());
+        check_depth(Dijkstra::new());
+        check_depth(BellmanFord::new());
+        check_depth(BeamSearch::new(4));
+        check_depth(IdaStar::new());
+        check_depth(Rbfs::new());
+        check_depth(SmaStar::new(16));
+    }
+}
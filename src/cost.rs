@@ -0,0 +1,234 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Debug, Display};
+
+/// The cost of a path, as summed along its edges and compared between paths.
+///
+/// `MAX` is the largest cost the type can hold, and a path may cost exactly
+/// that. Searchers keep track of unreached nodes separately rather than giving
+/// them a cost of `MAX`.
+pub trait Cost: Copy + Ord + Debug {
+    const ZERO: Self;
+    const MAX: Self;
+
+    #[must_use]
+    fn add(self, other: Self) -> Self;
+
+    /// Adds two costs, or returns `None` if the sum can't be represented.
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// Multiplies the cost by `factor`, rounding down, to inflate a
+    /// heuristic for weighted search.
+    #[must_use]
+    fn scale(self, factor: f64) -> Self;
+
+    /// The cost as an `f64`, for taking ratios between costs.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! integer_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                const ZERO: Self = 0;
+                const MAX: Self = <$t>::MAX;
+
+                fn add(self, other: Self) -> Self {
+                    self + other
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_precision_loss,
+                    clippy::cast_sign_loss,
+                    clippy::cast_lossless
+                )]
+                fn scale(self, factor: f64) -> Self {
+                    (factor * self as f64).floor() as $t
+                }
+
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+integer_cost!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// An `f64` ordered by `f64::total_cmp`, so it can be used as a cost.
+///
+/// Costs should be finite and not negative zero or NaN, which sort apart from
+/// the numbers they resemble.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TotalF64(pub f64);
+
+impl PartialEq for TotalF64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TotalF64 {}
+
+impl PartialOrd for TotalF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalF64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Display for TotalF64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<f64> for TotalF64 {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
+}
+
+impl Cost for TotalF64 {
+    const ZERO: Self = Self(0.0);
+    const MAX: Self = Self(f64::INFINITY);
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+
+    /// Fails if the sum is infinite or NaN.
+    fn checked_add(self, other: Self) -> Option<Self> {
+        let sum = self.0 + other.0;
+        sum.is_finite().then_some(Self(sum))
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Self(factor * self.0)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0
+    }
+}
+
+/// Compared lexicographically, so later parts only break ties between
+/// earlier ones, and summed part by part.
+impl<A: Cost, B: Cost> Cost for (A, B) {
+    const ZERO: Self = (A::ZERO, B::ZERO);
+    const MAX: Self = (A::MAX, B::MAX);
+
+    fn add(self, other: Self) -> Self {
+        (self.0.add(other.0), self.1.add(other.1))
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Some((self.0.checked_add(other.0)?, self.1.checked_add(other.1)?))
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        (self.0.scale(factor), self.1.scale(factor))
+    }
+
+    /// Only the most significant part counts.
+    fn to_f64(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+impl<A: Cost, B: Cost, C: Cost> Cost for (A, B, C) {
+    const ZERO: Self = (A::ZERO, B::ZERO, C::ZERO);
+    const MAX: Self = (A::MAX, B::MAX, C::MAX);
+
+    fn add(self, other: Self) -> Self {
+        (
+            self.0.add(other.0),
+            self.1.add(other.1),
+            self.2.add(other.2),
+        )
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Some((
+            self.0.checked_add(other.0)?,
+            self.1.checked_add(other.1)?,
+            self.2.checked_add(other.2)?,
+        ))
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        (
+            self.0.scale(factor),
+            self.1.scale(factor),
+            self.2.scale(factor),
+        )
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+/// The cost of reaching `to` through the edge from `from` was too large for
+/// the cost type. When a node's own priority overflows, it is both ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CostOverflow<N> {
+    pub from: N,
+    pub to: N,
+}
+
+impl<N: Display> Display for CostOverflow<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cost overflow from {} to {}", self.from, self.to)
+    }
+}
+
+impl<N: Display + Debug> Error for CostOverflow<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        assert_eq!(3i64.add(4), 7);
+        assert_eq!(7u8.scale(1.5), 10);
+        assert_eq!(5usize.add(usize::ZERO), 5);
+        assert_eq!(i64::MAX.checked_add(1), None);
+        assert_eq!(u8::MAX.checked_add(0), Some(u8::MAX));
+        assert!((7u32.to_f64() - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn floats() {
+        let mut costs = [2.5, -1.0, 0.25].map(TotalF64);
+        costs.sort();
+        assert_eq!(costs, [-1.0, 0.25, 2.5].map(TotalF64));
+        assert_eq!(TotalF64(0.1).add(TotalF64(0.2)), TotalF64(0.1 + 0.2));
+        assert!(TotalF64(f64::MAX) < TotalF64::MAX);
+        assert_eq!(TotalF64(f64::MAX).checked_add(TotalF64(f64::MAX)), None);
+    }
+
+    #[test]
+    fn tuples() {
+        assert!((1, 9) < (2, 0));
+        assert!((1, 2).add((0, 3)) < (1, 6));
+        assert_eq!((1u32, 2i64, 3u8).add((1, 1, 1)), (2, 3, 4));
+        assert_eq!(<(i64, i64)>::ZERO, (0, 0));
+        // a tiebreak overflowing is as much an error as the primary cost.
+        assert_eq!((0, i64::MAX).checked_add((1, 1)), None);
+        assert!(((3, 100).to_f64() - 3.0).abs() < 1e-9);
+    }
+}
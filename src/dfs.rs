@@ -1,11 +1,26 @@
 use std::collections::HashSet;
 
-use crate::{graph::Graph, graphsearcher::GraphSearcher};
+use crate::{
+    budget::DepthLimit,
+    graph::Graph,
+    graphsearcher::{GraphSearcher, SearchStep, StepSearcher},
+    observer::SearchObserver,
+    searchresult::SearchResult,
+};
 
 pub struct DFS<G: Graph> {
     visited: HashSet<G::Node>,
     path: Vec<G::Node>,
+    /// the edges between the nodes of `path`.
+    edges: Vec<G::Edge>,
+    /// the successors of the nodes on the path that are still to be entered,
+    /// the next one last.
+    successors: Vec<(G::Edge, G::Node)>,
+    /// where each node on the path starts in `successors`.
+    frames: Vec<usize>,
+    pending: Option<G::Node>,
     found: bool,
+    depth_limit: DepthLimit,
 }
 
 impl<G: Graph> DFS<G> {
@@ -14,13 +29,32 @@ impl<G: Graph> DFS<G> {
         Self {
             visited: HashSet::new(),
             path: Vec::new(),
+            edges: Vec::new(),
+            successors: Vec::new(),
+            frames: Vec::new(),
+            pending: None,
             found: false,
+            depth_limit: DepthLimit::new(),
         }
     }
 
     fn mark_visited(&mut self, node: G::Node) {
         self.visited.insert(node);
     }
+
+    fn visit(graph: &G, node: G::Node, visited: &mut HashSet<G::Node>) -> Option<G::Node> {
+        visited.insert(node);
+        if graph.is_goal(node) {
+            return Some(node);
+        }
+        let mut found = None;
+        graph.for_each_child(node, |neighbor| {
+            if found.is_none() && !visited.contains(&neighbor) {
+                found = Self::visit(graph, neighbor, visited);
+            }
+        });
+        found
+    }
 }
 
 impl<G: Graph> Default for DFS<G> {
@@ -29,37 +63,102 @@ impl<G: Graph> Default for DFS<G> {
     }
 }
 
-impl<G: Graph> GraphSearcher<G> for DFS<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
-        self.mark_visited(root);
-        self.path.push(root);
-        if graph.is_goal(root) {
-            self.found = true;
-            return Some(root);
-        }
-        for neighbor in graph.children(root) {
-            if let Some(node) = self.search_tracked(graph, neighbor) {
-                return Some(node);
+impl<G: Graph> StepSearcher<G> for DFS<G> {
+    fn start(&mut self, _graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.visited.clear();
+        self.path.clear();
+        self.edges.clear();
+        self.successors.clear();
+        self.frames.clear();
+        self.pending = Some(root);
+        self.found = false;
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        loop {
+            if let Some(node) = self.pending.take() {
+                self.mark_visited(node);
+                self.path.push(node);
+                if graph.is_goal(node) {
+                    observer.on_goal(node);
+                    self.found = true;
+                    self.successors.clear();
+                    self.frames.clear();
+                    return None;
+                }
+                if !self.depth_limit.expands(graph, node, self.path.len() - 1) {
+                    // it may be reached again by a shorter route.
+                    observer.on_prune(node);
+                    self.path.pop();
+                    self.edges.pop();
+                    self.visited.remove(&node);
+                    continue;
+                }
+                observer.on_expand(node);
+                let start = self.successors.len();
+                graph.for_each_successor(node, |edge, child| {
+                    observer.on_generate(node, child);
+                    self.successors.push((edge, child));
+                });
+                self.successors[start..].reverse();
+                self.frames.push(start);
+                return Some(SearchStep {
+                    node,
+                    children: Vec::new(),
+                    frontier: self.successors.len(),
+                    f: None,
+                });
+            }
+            let &start = self.frames.last()?;
+            let next = if self.successors.len() > start {
+                self.successors.pop()
+            } else {
+                None
+            };
+            if let Some((edge, next)) = next {
+                if self.visited.contains(&next) {
+                    observer.on_duplicate(next);
+                } else {
+                    self.edges.push(edge);
+                    self.pending = Some(next);
+                }
+            } else {
+                self.frames.pop();
+                self.path.pop();
+                self.edges.pop();
             }
         }
-        self.path.pop();
-        None
     }
 
-    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
-        if graph.is_goal(root) {
-            return Some(root);
-        }
-        for neighbor in graph.children(root) {
-            if let Some(node) = Self::search(graph, neighbor) {
-                return Some(node);
-            }
+    fn solution(&self) -> Option<G::Node> {
+        if self.found {
+            self.path.last().copied()
+        } else {
+            None
         }
-        None
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.visited.len()
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: Graph> GraphSearcher<G> for DFS<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::visit(graph, root, &mut HashSet::new())
     }
 
     fn is_visited(&self, node: G::Node) -> bool {
@@ -73,13 +172,35 @@ impl<G: Graph> GraphSearcher<G> for DFS<G> {
             None
         }
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        if self.found {
+            Some(self.edges.clone())
+        } else {
+            None
+        }
+    }
 }
 
 pub struct IterDeepening<G: Graph> {
     visited: HashSet<G::Node>,
     path: Vec<G::Node>,
+    /// the edges between the nodes of `path`.
+    edges: Vec<G::Edge>,
+    /// the successors of the nodes on the path that are still to be entered,
+    /// the next one last.
+    successors: Vec<(G::Edge, G::Node)>,
+    /// where each node on the path starts in `successors`, with the depth
+    /// left below it.
+    frames: Vec<(usize, usize)>,
+    pending: Option<(G::Node, usize)>,
+    /// the root while the search is running.
+    root: Option<G::Node>,
+    limit: usize,
+    /// whether the current iteration stopped short at the depth limit.
+    cutoff: bool,
     found: bool,
-    counter: usize,
+    depth_limit: DepthLimit,
 }
 
 impl<G: Graph> IterDeepening<G> {
@@ -88,47 +209,50 @@ impl<G: Graph> IterDeepening<G> {
         Self {
             visited: HashSet::new(),
             path: Vec::new(),
+            edges: Vec::new(),
+            successors: Vec::new(),
+            frames: Vec::new(),
+            pending: None,
+            root: None,
+            limit: 0,
+            cutoff: false,
             found: false,
-            counter: 0,
+            depth_limit: DepthLimit::new(),
         }
     }
 
-    fn dl_search_tracked(&mut self, graph: &G, root: G::Node, depth: usize) -> Option<G::Node> {
-        self.mark_visited(root);
-        self.path.push(root);
+    /// Returns the goal if one is found within `depth`, otherwise whether the
+    /// search was cut off by it.
+    fn dl_search(
+        graph: &G,
+        node: G::Node,
+        depth: usize,
+        path: &mut Vec<G::Node>,
+    ) -> Result<G::Node, bool> {
         if depth == 0 {
-            return None;
+            return Err(true);
         }
-        if graph.is_goal(root) {
-            return Some(root);
+        if graph.is_goal(node) {
+            return Ok(node);
         }
-        for neighbor in graph.children(root) {
-            if let Some(node) = self.dl_search_tracked(graph, neighbor, depth - 1) {
-                return Some(node);
+        let mut found = None;
+        let mut cutoff = false;
+        path.push(node);
+        graph.for_each_child(node, |neighbor| {
+            if found.is_some() || path.contains(&neighbor) {
+                return;
             }
-        }
-        self.path.pop();
-        None
-    }
-
-    fn dl_search(graph: &G, root: G::Node, depth: usize) -> Option<G::Node> {
-        if depth == 0 {
-            return None;
-        }
-        if graph.is_goal(root) {
-            return Some(root);
-        }
-        for neighbor in graph.children(root) {
-            if let Some(node) = Self::dl_search(graph, neighbor, depth - 1) {
-                return Some(node);
+            match Self::dl_search(graph, neighbor, depth - 1, path) {
+                Ok(goal) => found = Some(goal),
+                Err(cut) => cutoff |= cut,
             }
-        }
-        None
+        });
+        path.pop();
+        found.ok_or(cutoff)
     }
 
     fn mark_visited(&mut self, node: G::Node) {
         self.visited.insert(node);
-        self.counter += 1;
     }
 }
 
@@ -138,31 +262,131 @@ impl<G: Graph> Default for IterDeepening<G> {
     }
 }
 
-impl<G: Graph> GraphSearcher<G> for IterDeepening<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
-        for depth in 0.. {
-            self.path.clear();
-            if let Some(node) = self.dl_search_tracked(graph, root, depth) {
-                self.found = true;
-                return Some(node);
+impl<G: Graph> StepSearcher<G> for IterDeepening<G> {
+    fn start(&mut self, _graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.visited.clear();
+        self.path.clear();
+        self.edges.clear();
+        self.successors.clear();
+        self.frames.clear();
+        self.pending = Some((root, 0));
+        self.root = Some(root);
+        self.limit = 0;
+        self.cutoff = false;
+        self.found = false;
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        loop {
+            if let Some((node, depth)) = self.pending.take() {
+                self.mark_visited(node);
+                if depth == 0 {
+                    observer.on_prune(node);
+                    self.cutoff = true;
+                    self.edges.pop();
+                    continue;
+                }
+                self.path.push(node);
+                if graph.is_goal(node) {
+                    observer.on_goal(node);
+                    self.found = true;
+                    self.successors.clear();
+                    self.frames.clear();
+                    self.root = None;
+                    return None;
+                }
+                if !self.depth_limit.expands(graph, node, self.path.len() - 1) {
+                    observer.on_prune(node);
+                    self.path.pop();
+                    self.edges.pop();
+                    continue;
+                }
+                observer.on_expand(node);
+                let start = self.successors.len();
+                graph.for_each_successor(node, |edge, child| {
+                    observer.on_generate(node, child);
+                    self.successors.push((edge, child));
+                });
+                self.successors[start..].reverse();
+                self.frames.push((start, depth - 1));
+                return Some(SearchStep {
+                    node,
+                    children: Vec::new(),
+                    frontier: self.successors.len(),
+                    f: None,
+                });
+            }
+            if let Some(&(start, depth)) = self.frames.last() {
+                let next = if self.successors.len() > start {
+                    self.successors.pop()
+                } else {
+                    None
+                };
+                if let Some((edge, next)) = next {
+                    if self.path.contains(&next) {
+                        observer.on_duplicate(next);
+                    } else {
+                        self.edges.push(edge);
+                        self.pending = Some((next, depth));
+                    }
+                } else {
+                    self.frames.pop();
+                    self.path.pop();
+                    self.edges.pop();
+                }
+                continue;
+            }
+            // the iteration is over. Only go deeper if something was cut off.
+            let root = self.root?;
+            if !self.cutoff {
+                self.root = None;
+                return None;
             }
+            self.limit += 1;
+            self.cutoff = false;
+            self.pending = Some((root, self.limit));
         }
-        None // unreachable
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        if self.found {
+            self.path.last().copied()
+        } else {
+            None
+        }
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: Graph> GraphSearcher<G> for IterDeepening<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
     }
 
     fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        let mut path = Vec::new();
         for depth in 0.. {
-            if let Some(node) = Self::dl_search(graph, root, depth) {
-                return Some(node);
+            match Self::dl_search(graph, root, depth, &mut path) {
+                Ok(node) => return Some(node),
+                Err(false) => return None,
+                Err(true) => {}
             }
         }
         None // unreachable
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.counter
-    }
-
     fn is_visited(&self, node: G::Node) -> bool {
         self.visited.contains(&node)
     }
@@ -174,6 +398,14 @@ impl<G: Graph> GraphSearcher<G> for IterDeepening<G> {
             None
         }
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        if self.found {
+            Some(self.edges.clone())
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -190,7 +422,7 @@ mod tests {
     fn basic() {
         let graph = examplegraph::get_example_graph();
         let mut searcher = DFS::new();
-        let found = searcher.search_tracked(&graph, graph.root());
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
         assert!(found.is_some());
         assert!(graph.is_goal(found.unwrap()));
         assert_eq!(
@@ -208,7 +440,7 @@ mod tests {
     fn basic_itdeep() {
         let graph = examplegraph::get_example_graph();
         let mut searcher = IterDeepening::new();
-        let found = searcher.search_tracked(&graph, graph.root());
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
         assert!(found.is_some());
         assert!(graph.is_goal(found.unwrap()));
         assert_eq!(
@@ -221,4 +453,35 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn cyclic() {
+        let graph = examplegraph::get_cyclic_graph();
+        let path = [8, 3, 6, 7].map(ExampleNode::new);
+        let mut searcher = DFS::new();
+        assert_eq!(
+            searcher.search_tracked(&graph, graph.root()).goal(),
+            Some(path[3])
+        );
+        assert_eq!(searcher.path().unwrap(), path);
+        let mut searcher = IterDeepening::new();
+        assert_eq!(
+            searcher.search_tracked(&graph, graph.root()).goal(),
+            Some(path[3])
+        );
+        assert_eq!(searcher.path().unwrap(), path);
+
+        // 10, 14 and 13 form a loop with no way out to the goal.
+        let loop_root = ExampleNode::new(10);
+        assert!(DFS::new()
+            .search_tracked(&graph, loop_root)
+            .goal()
+            .is_none());
+        assert!(DFS::search(&graph, loop_root).is_none());
+        assert!(IterDeepening::new()
+            .search_tracked(&graph, loop_root)
+            .goal()
+            .is_none());
+        assert!(IterDeepening::search(&graph, loop_root).is_none());
+    }
 }
@@ -1,44 +1,206 @@
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::Debug;
+use std::hash::Hash;
 
+use crate::budget::DepthLimit;
+use crate::cost::{Cost, CostOverflow};
 use crate::graph::WeightedGraph;
-use crate::graphsearcher::GraphSearcher;
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
 use crate::heapelement::HeapElement;
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
 
-pub struct Dijkstra<G: WeightedGraph> {
-    distances: HashMap<G::Node, i64>,
+pub struct Dijkstra<G: WeightedGraph<C>, C: Cost = i64> {
+    distances: HashMap<G::Node, C>,
     parents: HashMap<G::Node, G::Node>,
-    max_frontier: usize,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<G::Node, G::Edge>,
+    /// nodes expanded at their current distance.
+    closed: HashSet<G::Node>,
+    frontier: BinaryHeap<HeapElement<G::Node, C>>,
     solution: Option<G::Node>,
+    /// the edge whose cost overflowed, ending the search.
+    overflow: Option<CostOverflow<G::Node>>,
+    /// whether goals are expanded like any other node, so the search settles
+    /// everything reachable.
+    exhaustive: bool,
+    depth_limit: DepthLimit,
 }
 
-impl<G: WeightedGraph> Debug for Dijkstra<G> {
+impl<G: WeightedGraph<C>, C: Cost> Debug for Dijkstra<G, C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Dijkstra")
     }
 }
 
-impl<G: WeightedGraph> Dijkstra<G> {
+impl<G: WeightedGraph<C>, C: Cost> Dijkstra<G, C> {
     #[must_use]
     pub fn new() -> Self {
         Self {
             distances: HashMap::new(),
             parents: HashMap::new(),
-            max_frontier: 1,
+            parent_edges: HashMap::new(),
+            closed: HashSet::new(),
+            frontier: BinaryHeap::new(),
             solution: None,
+            overflow: None,
+            exhaustive: false,
+            depth_limit: DepthLimit::new(),
         }
     }
 
-    pub const fn max_frontier(&self) -> usize {
-        self.max_frontier
+    pub const fn distances(&self) -> &HashMap<G::Node, C> {
+        &self.distances
     }
+}
 
-    pub const fn distances(&self) -> &HashMap<G::Node, i64> {
-        &self.distances
+impl<G: WeightedGraph<C>, C: Cost> Dijkstra<G, C> {
+    /// Finds the nearest goal from `root` without keeping any search state.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending edge if the cost of a route overflows.
+    pub fn try_search(graph: &G, root: G::Node) -> Result<Option<G::Node>, CostOverflow<G::Node>> {
+        uniform_cost_search(graph, root)
+    }
+
+    /// Runs Dijkstra's algorithm from `root` until the frontier is empty,
+    /// ignoring goals, and returns the resulting shortest-path tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending edge if the cost of a route overflows.
+    pub fn shortest_path_tree(graph: &G, root: G::Node) -> TreeResult<G::Node, G::Edge, C> {
+        let mut searcher = Self {
+            exhaustive: true,
+            ..Self::new()
+        };
+        searcher.start(graph, root);
+        let mut order = Vec::new();
+        while let Some(step) = searcher.step_observed(graph, &mut ()) {
+            order.push(step.node);
+        }
+        if let Some(overflow) = searcher.overflow {
+            return Err(overflow);
+        }
+
+        Ok(ShortestPathTree {
+            root,
+            distances: searcher.distances,
+            parents: searcher.parents,
+            parent_edges: searcher.parent_edges,
+            order,
+        })
+    }
+}
+
+/// Expands nodes in order of their cost from `root` until one is a goal. An
+/// overflow ends the search, since costs past it can't be trusted to find the
+/// nearest goal.
+fn uniform_cost_search<G: WeightedGraph<C>, C: Cost>(
+    graph: &G,
+    root: G::Node,
+) -> Result<Option<G::Node>, CostOverflow<G::Node>> {
+    let mut distances = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+
+    distances.insert(root, C::ZERO);
+    frontier.push(HeapElement::new(root, C::ZERO));
+
+    while let Some(HeapElement { node, cost }) = frontier.pop() {
+        if graph.is_goal(node) {
+            return Ok(Some(node));
+        }
+        let mut overflow = None;
+        graph.for_each_weighted_successor(node, |_, child, weight| {
+            if overflow.is_some() {
+                return;
+            }
+            let Some(cost_to_child) = cost.checked_add(weight) else {
+                overflow = Some(CostOverflow {
+                    from: node,
+                    to: child,
+                });
+                return;
+            };
+            if distances
+                .get(&child)
+                .is_none_or(|&known| cost_to_child < known)
+            {
+                distances.insert(child, cost_to_child);
+                frontier.push(HeapElement::new(child, cost_to_child));
+            }
+        });
+        if let Some(overflow) = overflow {
+            return Err(overflow);
+        }
     }
+    Ok(None)
+}
+
+/// The shortest-path tree from a root, or the edge whose cost overflowed.
+pub type TreeResult<N, E, C = i64> = Result<ShortestPathTree<N, E, C>, CostOverflow<N>>;
+
+/// Shortest distances and routes from a single root to every reachable node.
+#[derive(Clone, Debug)]
+pub struct ShortestPathTree<N, E, C = i64> {
+    root: N,
+    distances: HashMap<N, C>,
+    parents: HashMap<N, N>,
+    /// the edge each node was reached by.
+    parent_edges: HashMap<N, E>,
+    order: Vec<N>,
 }
 
-impl<G: WeightedGraph> Default for Dijkstra<G> {
+impl<N: Copy + Eq + Hash, E: Copy, C: Cost> ShortestPathTree<N, E, C> {
+    pub const fn root(&self) -> N {
+        self.root
+    }
+
+    pub const fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn contains(&self, node: N) -> bool {
+        self.distances.contains_key(&node)
+    }
+
+    pub fn distance_to(&self, node: N) -> Option<C> {
+        self.distances.get(&node).copied()
+    }
+
+    pub fn path_to(&self, node: N) -> Option<Vec<N>> {
+        if !self.contains(node) {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut n = node;
+        path.push(n);
+        while let Some(&parent) = self.parents.get(&n) {
+            path.push(parent);
+            n = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// The edges taken along `path_to(node)`, one fewer than its nodes.
+    pub fn edges_to(&self, node: N) -> Option<Vec<E>> {
+        let path = self.path_to(node)?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
+
+    /// Iterates over the nodes and their distances in the order they were settled.
+    pub fn settled(&self) -> impl Iterator<Item = (N, C)> + '_ {
+        self.order.iter().map(|&n| (n, self.distances[&n]))
+    }
+}
+
+impl<G: WeightedGraph<C>, C: Cost> Default for Dijkstra<G, C> {
     fn default() -> Self {
         Self::new()
     }
@@ -61,59 +223,114 @@ impl<G: WeightedGraph> Default for Dijkstra<G> {
 //             else if n is in frontier with higher cost
 //                 replace existing node with n
 
-impl<G: WeightedGraph> GraphSearcher<G> for Dijkstra<G> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node> {
+impl<G: WeightedGraph<C>, C: Cost> StepSearcher<G, C> for Dijkstra<G, C> {
+    fn start(&mut self, _graph: &G, root: G::Node) {
+        self.depth_limit.reset();
         self.distances.clear();
         self.parents.clear();
-        self.max_frontier = 1;
+        self.parent_edges.clear();
+        self.closed.clear();
+        self.frontier.clear();
+        self.solution = None;
+        self.overflow = None;
 
-        let mut frontier = BinaryHeap::new();
-        frontier.push(HeapElement::new(root, 0));
+        self.frontier.push(HeapElement::new(root, C::ZERO));
 
-        self.distances.insert(root, 0);
+        self.distances.insert(root, C::ZERO);
+    }
 
-        while let Some(HeapElement { node, cost }) = frontier.pop() {
-            if graph.is_goal(node) {
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node, C>> {
+        let (node, cost) = loop {
+            let HeapElement { node, cost } = self.frontier.pop()?;
+            if !self.exhaustive && graph.is_goal(node) {
+                observer.on_goal(node);
                 self.solution = Some(node);
-                return Some(node);
+                self.frontier.clear();
+                return None;
             }
-            for child in graph.children(node) {
-                let cost_to_child = cost + graph.edge_weight(node, child);
-                if cost_to_child < self.distances.get(&child).copied().unwrap_or(i64::MAX) {
-                    self.parents.insert(child, node);
-                    self.distances.insert(child, cost_to_child);
-                    frontier.push(HeapElement::new(child, cost_to_child));
+            // skip entries left behind when a node was reached more cheaply.
+            if !self.closed.insert(node) {
+                continue;
+            }
+            if self.depth_limit.expands_from(graph, node, &self.parents) {
+                break (node, cost);
+            }
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        graph.for_each_weighted_successor(node, |edge, child, weight| {
+            if self.overflow.is_some() {
+                return;
+            }
+            observer.on_generate(node, child);
+            let Some(cost_to_child) = cost.checked_add(weight) else {
+                self.overflow = Some(CostOverflow {
+                    from: node,
+                    to: child,
+                });
+                return;
+            };
+            if self
+                .distances
+                .get(&child)
+                .is_none_or(|&known| cost_to_child < known)
+            {
+                if self.closed.remove(&child) {
+                    observer.on_reopen(child);
                 }
+                self.parents.insert(child, node);
+                self.parent_edges.insert(child, edge);
+                self.distances.insert(child, cost_to_child);
+                self.frontier.push(HeapElement::new(child, cost_to_child));
+            } else {
+                observer.on_duplicate(child);
             }
-            self.max_frontier = std::cmp::max(self.max_frontier, frontier.len());
+        });
+        if self.overflow.is_some() {
+            self.frontier.clear();
         }
-        None
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.frontier.len(),
+            f: Some(cost),
+        })
     }
 
-    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
-        let mut distances = HashMap::new();
-        let mut frontier = BinaryHeap::new();
+    fn solution(&self) -> Option<G::Node> {
+        self.solution
+    }
 
-        distances.insert(root, 0);
-        frontier.push(HeapElement::new(root, 0));
+    fn solution_cost(&self, _graph: &G) -> Option<C> {
+        self.solution.map(|goal| self.distances[&goal])
+    }
 
-        while let Some(HeapElement { node, cost }) = frontier.pop() {
-            if graph.is_goal(node) {
-                return Some(node);
-            }
-            for child in graph.children(node) {
-                let cost_to_child = cost + graph.edge_weight(node, child);
-                if cost_to_child < distances.get(&child).copied().unwrap_or(i64::MAX) {
-                    distances.insert(child, cost_to_child);
-                    frontier.push(HeapElement::new(child, cost_to_child));
-                }
-            }
-        }
-        None
+    fn cost_overflow(&self) -> Option<CostOverflow<G::Node>> {
+        self.overflow
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph<C>, C: Cost> GraphSearcher<G, C> for Dijkstra<G, C> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G, C> {
+        self.search_observed(graph, root, &mut ())
     }
 
-    fn nodes_visited(&self) -> usize {
-        self.distances.len()
+    /// Gives `None` on overflow as well; use `try_search` to tell the two
+    /// apart.
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::try_search(graph, root).ok().flatten()
     }
 
     fn is_visited(&self, node: G::Node) -> bool {
@@ -132,20 +349,183 @@ impl<G: WeightedGraph> GraphSearcher<G> for Dijkstra<G> {
         path.reverse();
         Some(path)
     }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let path = self.path()?;
+        Some(path[1..].iter().map(|n| self.parent_edges[n]).collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::budget::SearchOutcome;
+    use crate::cost::TotalF64;
     use crate::examplegraph::{get_example_graph, ExampleNode};
     use crate::graph::Graph;
     use crate::graphsearcher::GraphSearcher;
 
+    /// A graph that may have several edges between the same two nodes.
+    struct MultiGraph {
+        edges: Vec<(usize, usize, i64)>,
+        goal: usize,
+    }
+
+    impl Graph for MultiGraph {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.successors(node).into_iter().map(|(_, n)| n).collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            self.successors(node).into_iter().map(|(e, _)| e).collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            (0..self.edges.len())
+                .filter(|&i| self.edges[i].0 == node)
+                .map(|i| (i, self.edges[i].1))
+                .collect()
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.goal
+        }
+    }
+
+    impl WeightedGraph for MultiGraph {
+        fn edge_weight(&self, from: Self::Node, to: Self::Node) -> i64 {
+            self.edges
+                .iter()
+                .filter(|e| e.0 == from && e.1 == to)
+                .map(|e| e.2)
+                .min()
+                .unwrap()
+        }
+
+        fn weighted_successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node, i64)> {
+            self.successors(node)
+                .into_iter()
+                .map(|(e, n)| (e, n, self.edges[e].2))
+                .collect()
+        }
+    }
+
+    /// Travel times in minutes between junctions.
+    struct Roads {
+        roads: Vec<(usize, usize, f64)>,
+        goal: usize,
+    }
+
+    impl Graph for Roads {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.roads
+                .iter()
+                .filter(|r| r.0 == node)
+                .map(|r| r.1)
+                .collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            (0..self.roads.len())
+                .filter(|&i| self.roads[i].0 == node)
+                .collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            self.roads
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.0 == node)
+                .map(|(i, r)| (i, r.1))
+                .collect()
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.goal
+        }
+    }
+
+    impl WeightedGraph<TotalF64> for Roads {
+        fn edge_weight(&self, from: Self::Node, to: Self::Node) -> TotalF64 {
+            let road = self.roads.iter().find(|r| r.0 == from && r.1 == to);
+            TotalF64(road.unwrap().2)
+        }
+    }
+
+    #[test]
+    fn float_costs() {
+        let graph = Roads {
+            roads: vec![(0, 1, 0.4), (1, 3, 0.35), (0, 2, 0.3), (2, 3, 0.5)],
+            goal: 3,
+        };
+        let mut searcher = Dijkstra::new();
+        let result = searcher.search_tracked(&graph, 0);
+        assert_eq!(result.path.unwrap(), &[0, 1, 3]);
+        assert_eq!(result.cost, Some(TotalF64(0.4 + 0.35)));
+        assert_eq!(searcher.distances()[&2], TotalF64(0.3));
+        let tree = Dijkstra::shortest_path_tree(&graph, 0).unwrap();
+        assert_eq!(tree.distance_to(3), result.cost);
+    }
+
+    #[test]
+    fn parallel_edges() {
+        let graph = MultiGraph {
+            edges: vec![(0, 1, 5), (0, 1, 2), (1, 2, 4), (1, 2, 1), (0, 2, 9)],
+            goal: 2,
+        };
+        let result = Dijkstra::new().search_tracked(&graph, 0);
+        assert_eq!(result.path.unwrap(), &[0, 1, 2]);
+        assert_eq!(result.edges.unwrap(), &[1, 3]);
+        assert_eq!(result.cost, Some(3));
+        let tree = Dijkstra::shortest_path_tree(&graph, 0).unwrap();
+        assert_eq!(tree.edges_to(2).unwrap(), &[1, 3]);
+        assert_eq!(tree.edges_to(0).unwrap(), &[]);
+    }
+
+    #[test]
+    fn extreme_weights() {
+        let graph = MultiGraph {
+            edges: vec![(0, 1, i64::MAX - 1), (1, 2, 1)],
+            goal: 2,
+        };
+        let result = Dijkstra::new().search_tracked(&graph, 0);
+        assert_eq!(result.cost, Some(i64::MAX));
+
+        let graph = MultiGraph {
+            edges: vec![(0, 1, i64::MAX - 1), (1, 2, 5)],
+            goal: 2,
+        };
+        let result = Dijkstra::new().search_tracked(&graph, 0);
+        let overflow = CostOverflow { from: 1, to: 2 };
+        assert_eq!(result.outcome, SearchOutcome::Overflow(overflow));
+        assert!(result.path.is_none());
+        assert_eq!(Dijkstra::search(&graph, 0), None);
+        assert_eq!(Dijkstra::try_search(&graph, 0), Err(overflow));
+        assert_eq!(
+            Dijkstra::shortest_path_tree(&graph, 0).err(),
+            Some(overflow)
+        );
+    }
+
     #[test]
     fn basic() {
         let graph = get_example_graph();
         let mut searcher = Dijkstra::new();
-        let found = searcher.search_tracked(&graph, graph.root());
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
         assert!(found.is_some());
         assert!(graph.is_goal(found.unwrap()));
         assert_eq!(
@@ -158,4 +538,27 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn shortest_path_tree() {
+        let graph = get_example_graph();
+        let tree = Dijkstra::shortest_path_tree(&graph, graph.root()).unwrap();
+        assert_eq!(tree.len(), 9);
+        assert_eq!(tree.distance_to(ExampleNode::new(13)), Some(3));
+        assert_eq!(
+            tree.path_to(ExampleNode::new(13)).unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(10),
+                ExampleNode::new(14),
+                ExampleNode::new(13)
+            ]
+        );
+        assert_eq!(tree.edges_to(ExampleNode::new(13)).unwrap().len(), 3);
+        assert!(tree.path_to(ExampleNode::new(99)).is_none());
+        assert!(tree.edges_to(ExampleNode::new(99)).is_none());
+        let distances = tree.settled().map(|(_, d)| d).collect::<Vec<_>>();
+        assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(tree.settled().next(), Some((graph.root(), 0)));
+    }
 }
@@ -1,6 +1,6 @@
 use std::{fmt::Display, hash::Hash};
 
-use crate::graph::{Graph, HeuristicGraph, WeightedGraph};
+use crate::graph::{AdversarialGraph, Graph, HeuristicGraph, ReversibleGraph, Side, WeightedGraph};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct ExampleNode {
@@ -65,7 +65,7 @@ impl ExampleGraph {
     }
 
     #[cfg(test)]
-    fn set_goal(&mut self, node: ExampleNode) {
+    const fn set_goal(&mut self, node: ExampleNode) {
         self.goal_id = node.id;
     }
 }
@@ -98,11 +98,34 @@ impl Graph for ExampleGraph {
             .collect()
     }
 
+    /// In the same order as `children`, which lists nodes in the order they
+    /// were added rather than their edges.
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        self.children(node)
+            .into_iter()
+            .map(|child| (ExampleEdge::new(node.id, child.id), child))
+            .collect()
+    }
+
     fn is_goal(&self, node: Self::Node) -> bool {
         node.id == self.goal_id
     }
 }
 
+impl ReversibleGraph for ExampleGraph {
+    fn parents(&self, node: Self::Node) -> Vec<Self::Node> {
+        self.nodes
+            .iter()
+            .filter(move |n| {
+                self.edges
+                    .iter()
+                    .any(|e| e.from_id == n.id && e.to_id == node.id)
+            })
+            .copied()
+            .collect()
+    }
+}
+
 impl WeightedGraph for ExampleGraph {
     fn edge_weight(&self, _from: Self::Node, _to: Self::Node) -> i64 {
         1
@@ -142,3 +165,66 @@ pub fn get_example_graph() -> ExampleGraph {
 
     graph
 }
+
+/// The example graph with edges back from 3 to the root and from 13 to 10.
+#[cfg(test)]
+pub fn get_cyclic_graph() -> ExampleGraph {
+    let mut graph = get_example_graph();
+    graph.add_edge(ExampleEdge::new(3, 8));
+    graph.add_edge(ExampleEdge::new(13, 10));
+    graph
+}
+
+/// Nim where each player takes one to three stones, and whoever takes the
+/// last stone wins. Nodes are `stones * 2 + player`, `Max` being player 0.
+pub struct Nim;
+
+impl Graph for Nim {
+    type Node = usize;
+    type Edge = usize;
+
+    fn root(&self) -> Self::Node {
+        10
+    }
+
+    fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+        let (stones, player) = (node / 2, node % 2);
+        (1..=std::cmp::min(3, stones))
+            .map(|take| (stones - take) * 2 + (1 - player))
+            .collect()
+    }
+
+    fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+        (1..=std::cmp::min(3, node / 2)).collect()
+    }
+
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        let (stones, player) = (node / 2, node % 2);
+        (1..=std::cmp::min(3, stones))
+            .map(|take| (take, (stones - take) * 2 + (1 - player)))
+            .collect()
+    }
+
+    fn is_goal(&self, node: Self::Node) -> bool {
+        node / 2 == 0
+    }
+}
+
+impl AdversarialGraph for Nim {
+    fn side_to_move(&self, node: Self::Node) -> Side {
+        if node % 2 == 0 {
+            Side::Max
+        } else {
+            Side::Min
+        }
+    }
+
+    fn evaluate(&self, node: Self::Node) -> i64 {
+        if node / 2 == 0 {
+            // the side to move has no stones left to take, and has lost.
+            -self.side_to_move(node).sign()
+        } else {
+            0
+        }
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::{fmt::Debug, fmt::Display, hash::Hash};
 
+use crate::cost::Cost;
+
 pub trait Graph {
     type Node: Copy + Eq + Hash + Display + Debug;
     type Edge: Copy + Eq + Hash + Display + Debug;
@@ -10,21 +12,127 @@ pub trait Graph {
     fn children(&self, node: Self::Node) -> Vec<Self::Node>;
     fn edges(&self, node: Self::Node) -> Vec<Self::Edge>;
     fn is_goal(&self, node: Self::Node) -> bool;
+
+    /// The edges out of `node`, each with the child it leads to, in the
+    /// order of `children`.
+    ///
+    /// Searchers that label their paths with edges expand through this, so
+    /// it must list every child. There is no default, since `edges` and
+    /// `children` need not line up.
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)>;
+
+    /// Calls `f` with each child of `node`, in the order of `children`.
+    ///
+    /// The default walks the `Vec` from `children`. Graphs that can generate
+    /// children in place should override it, so that searchers can expand a
+    /// node without allocating.
+    fn for_each_child(&self, node: Self::Node, mut f: impl FnMut(Self::Node)) {
+        for child in self.children(node) {
+            f(child);
+        }
+    }
+
+    /// Like `for_each_child`, with the edge to each child as in `successors`.
+    fn for_each_successor(&self, node: Self::Node, mut f: impl FnMut(Self::Edge, Self::Node)) {
+        for (edge, child) in self.successors(node) {
+            f(edge, child);
+        }
+    }
 }
 
+/// A graph whose edges cost `C`, which is `i64` unless the graph says
+/// otherwise.
 #[allow(clippy::module_name_repetitions)]
-pub trait WeightedGraph: Graph {
-    fn edge_weight(&self, from: Self::Node, to: Self::Node) -> i64;
+pub trait WeightedGraph<C: Cost = i64>: Graph {
+    fn edge_weight(&self, from: Self::Node, to: Self::Node) -> C;
 
-    fn path_cost(&self, path: &[Self::Node]) -> i64 {
+    /// The successors of `node` as (edge, child, cost) triples.
+    ///
+    /// By default each edge costs the `edge_weight` between the nodes it
+    /// joins. Graphs with parallel edges of different costs should override
+    /// it, since `edge_weight` can't tell them apart.
+    fn weighted_successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node, C)> {
+        self.successors(node)
+            .into_iter()
+            .map(|(edge, child)| (edge, child, self.edge_weight(node, child)))
+            .collect()
+    }
+
+    /// Like `for_each_successor`, with the cost of each edge as in
+    /// `weighted_successors`.
+    fn for_each_weighted_successor(
+        &self,
+        node: Self::Node,
+        mut f: impl FnMut(Self::Edge, Self::Node, C),
+    ) {
+        for (edge, child, weight) in self.weighted_successors(node) {
+            f(edge, child, weight);
+        }
+    }
+
+    fn path_cost(&self, path: &[Self::Node]) -> C {
         path.iter()
             .zip(path.iter().skip(1))
             .map(|(a, b)| self.edge_weight(*a, *b))
-            .sum()
+            .fold(C::ZERO, C::add)
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub trait HeuristicGraph<C: Cost = i64>: Graph {
+    /// An estimate of the cost from `node` to the nearest goal. `C::MAX`
+    /// means no goal can be reached from `node`, and searchers prune it.
+    fn heuristic(&self, node: Self::Node) -> C;
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub trait ReversibleGraph: Graph {
+    fn parents(&self, node: Self::Node) -> Vec<Self::Node>;
+
+    /// The edges into `node`, each with the parent it leads from.
+    fn predecessors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        self.parents(node)
+            .into_iter()
+            .flat_map(|parent| {
+                self.successors(parent)
+                    .into_iter()
+                    .filter(move |&(_, child)| child == node)
+                    .map(move |(edge, _)| (edge, parent))
+            })
+            .collect()
+    }
+}
+
+/// The player to move in an adversarial graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Max,
+    Min,
+}
+
+impl Side {
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Max => Self::Min,
+            Self::Min => Self::Max,
+        }
+    }
+
+    /// The factor that turns a score from `Max`'s perspective into one from this side's.
+    #[must_use]
+    pub const fn sign(self) -> i64 {
+        match self {
+            Self::Max => 1,
+            Self::Min => -1,
+        }
     }
 }
 
 #[allow(clippy::module_name_repetitions)]
-pub trait HeuristicGraph: Graph {
-    fn heuristic(&self, node: Self::Node) -> i64;
+pub trait AdversarialGraph: Graph {
+    fn side_to_move(&self, node: Self::Node) -> Side;
+    /// Scores a node from `Max`'s perspective. Exact for terminal nodes (those
+    /// without children), and a heuristic estimate at the search horizon.
+    fn evaluate(&self, node: Self::Node) -> i64;
 }
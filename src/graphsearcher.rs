@@ -1,9 +1,385 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use crate::budget::{BudgetLimit, SearchBudget, SearchOutcome};
+use crate::cost::{Cost, CostOverflow};
 use crate::graph::Graph;
+use crate::observer::{CountingObserver, SearchObserver};
+use crate::searchresult::{branching_factor, SearchResult, SearchStats};
 
-pub trait GraphSearcher<G: Graph> {
-    fn search_tracked(&mut self, graph: &G, root: G::Node) -> Option<G::Node>;
+/// A searcher over `G`, which reports path costs as `C`.
+pub trait GraphSearcher<G: Graph, C: Cost = i64> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G, C>;
     fn search(graph: &G, root: G::Node) -> Option<G::Node>;
-    fn nodes_visited(&self) -> usize;
     fn is_visited(&self, node: G::Node) -> bool;
     fn path(&self) -> Option<Vec<G::Node>>;
+    /// The edges taken along `path`, one fewer than its nodes.
+    fn path_edges(&self) -> Option<Vec<G::Edge>>;
+}
+
+/// One node expansion made by a step-by-step search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchStep<N, C = i64> {
+    pub node: N,
+    /// The successors generated by this expansion. Only `step` fills this
+    /// in: `step_observed` leaves it empty, since its observer sees each
+    /// child through `on_generate`.
+    pub children: Vec<N>,
+    /// The number of nodes waiting to be expanded afterwards.
+    pub frontier: usize,
+    /// The f-value of the expanded node, for searchers that order nodes by
+    /// one. Best-first searchers always expand the best f on the frontier.
+    pub f: Option<C>,
+}
+
+/// A searcher that can be driven one expansion at a time, so the caller can
+/// interleave the search with other work or stop it early.
+///
+/// The same graph must be passed to `start` and to every `step` of a search.
+pub trait StepSearcher<G: Graph, C: Cost = i64>: GraphSearcher<G, C> {
+    /// Resets the searcher to begin a search from `root`.
+    fn start(&mut self, graph: &G, root: G::Node);
+
+    /// Makes the next expansion, or returns `None` once the search is over.
+    fn step(&mut self, graph: &G) -> Option<SearchStep<G::Node, C>> {
+        let mut children = GeneratedChildren(Vec::new());
+        let step = self.step_observed(graph, &mut children)?;
+        Some(SearchStep {
+            children: children.0,
+            ..step
+        })
+    }
+
+    /// Makes the next expansion like `step`, reporting what happens to
+    /// `observer`.
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node, C>>;
+
+    /// The goal found by the search, once it is over.
+    fn solution(&self) -> Option<G::Node>;
+
+    /// The cost of the path to the goal found, for searchers that weigh
+    /// edges.
+    fn solution_cost(&self, _graph: &G) -> Option<C> {
+        None
+    }
+
+    /// The edge whose cost overflowed, if that ended the last search.
+    fn cost_overflow(&self) -> Option<CostOverflow<G::Node>> {
+        None
+    }
+
+    /// A node on the negative-cost cycle that ended the last search, for
+    /// searchers that allow negative edges.
+    fn negative_cycle_node(&self) -> Option<G::Node> {
+        None
+    }
+
+    /// Runs a search from `root` to completion like `search_tracked`,
+    /// reporting its progress to `observer`.
+    fn search_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        observer: &mut O,
+    ) -> SearchResult<G, C> {
+        run(self, graph, root, &SearchBudget::new(), observer)
+    }
+
+    /// Stops the searcher expanding nodes `max_depth` edges from the root,
+    /// so no path it finds is longer, or lifts the limit with `None`.
+    fn set_max_depth(&mut self, max_depth: Option<usize>);
+
+    /// Whether the last search left nodes unexpanded at the depth limit.
+    fn depth_cut_off(&self) -> bool;
+
+    /// Runs a search from `root` until it finishes or `budget` runs out.
+    ///
+    /// The budget's depth limit replaces any set with `set_max_depth`, for
+    /// this search only.
+    fn search_within(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        budget: &SearchBudget,
+    ) -> SearchResult<G, C> {
+        self.search_within_observed(graph, root, budget, &mut ())
+    }
+
+    /// Runs a search like `search_within`, reporting its progress to
+    /// `observer`.
+    fn search_within_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        budget: &SearchBudget,
+        observer: &mut O,
+    ) -> SearchResult<G, C> {
+        self.set_max_depth(budget.max_depth());
+        let result = run(self, graph, root, budget, observer);
+        self.set_max_depth(None);
+        result
+    }
+
+    /// Starts a search from `root`, returning an iterator over its
+    /// expansions.
+    fn steps<'a>(&'a mut self, graph: &'a G, root: G::Node) -> Steps<'a, G, Self, C>
+    where
+        Self: Sized,
+    {
+        self.start(graph, root);
+        Steps {
+            searcher: self,
+            graph,
+            cost: PhantomData,
+        }
+    }
+}
+
+/// Collects the children generated by an expansion, for `StepSearcher::step`.
+struct GeneratedChildren<N>(Vec<N>);
+
+impl<G: Graph> SearchObserver<G> for GeneratedChildren<G::Node> {
+    fn on_generate(&mut self, _parent: G::Node, child: G::Node) {
+        self.0.push(child);
+    }
+}
+
+/// Steps `searcher` from `root` until the search is over or a limit of
+/// `budget` other than its depth is reached, measuring it as it goes.
+fn run<G, C, S, O>(
+    searcher: &mut S,
+    graph: &G,
+    root: G::Node,
+    budget: &SearchBudget,
+    observer: &mut O,
+) -> SearchResult<G, C>
+where
+    G: Graph,
+    C: Cost,
+    S: StepSearcher<G, C> + ?Sized,
+    O: SearchObserver<G>,
+{
+    let started = Instant::now();
+    let mut observers = (CountingObserver::new(), observer);
+    let mut max_frontier = 1;
+    let mut steps = 0;
+    searcher.start(graph, root);
+    let outcome = loop {
+        if let Some(limit) = budget.check(steps) {
+            break searcher
+                .solution()
+                .map_or(SearchOutcome::Exhausted(limit), SearchOutcome::Found);
+        }
+        let Some(step) = searcher.step_observed(graph, &mut observers) else {
+            break match (
+                searcher.solution(),
+                searcher.cost_overflow(),
+                searcher.negative_cycle_node(),
+            ) {
+                (Some(goal), _, _) => SearchOutcome::Found(goal),
+                (None, Some(overflow), _) => SearchOutcome::Overflow(overflow),
+                (None, None, Some(node)) => SearchOutcome::NegativeCycle(node),
+                (None, None, None) if searcher.depth_cut_off() => {
+                    SearchOutcome::Exhausted(BudgetLimit::Depth)
+                }
+                (None, None, None) => SearchOutcome::NotFound,
+            };
+        };
+        max_frontier = std::cmp::max(max_frontier, step.frontier);
+        steps += 1;
+    };
+    let elapsed = started.elapsed();
+
+    let (counts, _) = observers;
+    let (path, edges) = match outcome {
+        SearchOutcome::Found(_) => (searcher.path(), searcher.path_edges()),
+        SearchOutcome::NotFound
+        | SearchOutcome::Exhausted(_)
+        | SearchOutcome::Overflow(_)
+        | SearchOutcome::NegativeCycle(_) => (None, None),
+    };
+    SearchResult {
+        outcome,
+        edges,
+        cost: path.as_ref().and_then(|_| searcher.solution_cost(graph)),
+        stats: SearchStats {
+            expanded: counts.expanded,
+            generated: counts.generated,
+            duplicates: counts.duplicates,
+            reopened: counts.reopened,
+            max_frontier,
+            elapsed,
+            branching_factor: path
+                .as_ref()
+                .and_then(|path| branching_factor(counts.generated, path.len() - 1)),
+        },
+        path,
+    }
+}
+
+/// The expansions of a search, from `StepSearcher::steps`.
+#[derive(Debug)]
+pub struct Steps<'a, G: Graph, S: StepSearcher<G, C>, C: Cost = i64> {
+    searcher: &'a mut S,
+    graph: &'a G,
+    cost: PhantomData<C>,
+}
+
+impl<G: Graph, S: StepSearcher<G, C>, C: Cost> Steps<'_, G, S, C> {
+    #[must_use]
+    pub const fn searcher(&self) -> &S {
+        self.searcher
+    }
+
+    /// The goal found, once the iterator is exhausted.
+    #[must_use]
+    pub fn solution(&self) -> Option<G::Node> {
+        self.searcher.solution()
+    }
+}
+
+impl<G: Graph, S: StepSearcher<G, C>, C: Cost> Iterator for Steps<'_, G, S, C> {
+    type Item = SearchStep<G::Node, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.searcher.step(self.graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::beam::BeamSearch;
+    use crate::bellmanford::BellmanFord;
+    use crate::bestfirst::BestFirstSearch;
+    use crate::bfs::BFS;
+    use crate::dfs::{IterDeepening, DFS};
+    use crate::dijkstra::Dijkstra;
+    use crate::examplegraph::{get_example_graph, ExampleEdge, ExampleGraph, ExampleNode};
+    use crate::idastar::IdaStar;
+    use crate::rbfs::Rbfs;
+    use crate::smastar::SmaStar;
+
+    /// Steps a searcher to the end, checking it agrees with `search_tracked`.
+    fn run<S: StepSearcher<ExampleGraph>>(
+        mut searcher: S,
+        root: ExampleNode,
+    ) -> (Vec<SearchStep<ExampleNode>>, Option<ExampleNode>) {
+        let graph = get_example_graph();
+        let mut steps = searcher.steps(&graph, root);
+        let expansions = steps.by_ref().collect::<Vec<_>>();
+        let solution = steps.solution();
+        let path = searcher.path();
+        let result = searcher.search_tracked(&graph, root);
+        assert_eq!(result.goal(), solution);
+        assert_eq!(result.path, path);
+        assert_eq!(searcher.path(), path);
+        assert_eq!(result.edges, searcher.path_edges());
+        if let (Some(path), Some(edges)) = (path, result.edges) {
+            assert_eq!(edges.len() + 1, path.len());
+            assert!(path
+                .windows(2)
+                .zip(edges)
+                .all(|(w, edge)| graph.successors(w[0]).contains(&(edge, w[1]))));
+        }
+        (expansions, solution)
+    }
+
+    #[test]
+    fn bfs_steps() {
+        let graph = get_example_graph();
+        let (steps, solution) = run(BFS::new(), graph.root());
+        assert_eq!(solution, Some(ExampleNode::new(7)));
+        let expanded = steps.iter().map(|s| s.node).collect::<Vec<_>>();
+        assert_eq!(expanded, [8, 3, 10, 1, 6, 14, 4].map(ExampleNode::new));
+        assert_eq!(steps[0].children, [3, 10].map(ExampleNode::new));
+        let frontiers = steps.iter().map(|s| s.frontier).collect::<Vec<_>>();
+        assert_eq!(frontiers, [2, 3, 3, 2, 3, 3, 2]);
+        assert!(steps.iter().all(|s| s.f.is_none()));
+    }
+
+    #[test]
+    fn children_only_from_step() {
+        let graph = get_example_graph();
+        let mut dfs = DFS::new();
+        dfs.start(&graph, graph.root());
+        let step = dfs.step_observed(&graph, &mut ()).unwrap();
+        assert!(step.children.is_empty());
+        assert_eq!(step.frontier, 2);
+
+        let children = [3, 10].map(ExampleNode::new);
+        let mut dfs = DFS::new();
+        assert_eq!(
+            dfs.steps(&graph, graph.root()).next().unwrap().children,
+            children
+        );
+        let mut idastar = IdaStar::new();
+        let first = idastar.steps(&graph, graph.root()).next().unwrap();
+        assert_eq!(first.children, children);
+    }
+
+    #[test]
+    fn every_searcher() {
+        let graph = get_example_graph();
+        let goal = Some(ExampleNode::new(7));
+        assert_eq!(run(DFS::new(), graph.root()).0.len(), 5);
+        assert_eq!(run(IterDeepening::new(), graph.root()).1, goal);
+        assert_eq!(run(BestFirstSearch::new(), graph.root()).1, goal);
+        assert_eq!(run(BellmanFord::new(), graph.root()).1, goal);
+        assert_eq!(run(BeamSearch::with_path_cost(2), graph.root()).1, goal);
+        assert_eq!(run(IdaStar::new(), graph.root()).1, goal);
+        assert_eq!(run(Rbfs::new(), graph.root()).1, goal);
+        assert_eq!(run(SmaStar::new(4), graph.root()).1, goal);
+        for (steps, solution) in [
+            run(AStar::new(), graph.root()),
+            run(Dijkstra::new(), graph.root()),
+        ] {
+            assert_eq!(solution, goal);
+            // best-first searchers expand in order of f.
+            let f = steps.iter().map(|s| s.f.unwrap()).collect::<Vec<_>>();
+            assert!(f.windows(2).all(|w| w[0] <= w[1]));
+        }
+
+        // 7 can't be reached from 10, and every search must say so.
+        let root = ExampleNode::new(10);
+        assert_eq!(run(IterDeepening::new(), root).0.len(), 1 + 2 + 3);
+        assert_eq!(run(IdaStar::new(), root).1, None);
+        assert_eq!(run(Rbfs::new(), root).1, None);
+        assert_eq!(run(SmaStar::new(100), root).1, None);
+    }
+
+    #[test]
+    fn path_edges() {
+        let graph = get_example_graph();
+        let mut searcher = BFS::new();
+        let result = searcher.search_tracked(&graph, graph.root());
+        let edges = [(8, 3), (3, 6), (6, 7)].map(|(from, to)| ExampleEdge::new(from, to));
+        assert_eq!(result.edges.unwrap(), edges);
+        assert!(BFS::new()
+            .search_tracked(&graph, ExampleNode::new(10))
+            .edges
+            .is_none());
+        // a path of one node takes no edges.
+        let result = BFS::new().search_tracked(&graph, ExampleNode::new(7));
+        assert_eq!(result.edges, Some(Vec::new()));
+    }
+
+    #[test]
+    fn stop_early() {
+        let graph = get_example_graph();
+        let mut searcher = AStar::new();
+        let mut steps = searcher.steps(&graph, graph.root());
+        assert_eq!(steps.next().unwrap().node, graph.root());
+        assert!(steps.next().is_some());
+        assert_eq!(steps.solution(), None);
+        assert!(steps.searcher().is_visited(ExampleNode::new(3)));
+        assert!(steps.by_ref().count() > 0);
+        assert_eq!(steps.solution(), Some(ExampleNode::new(7)));
+        assert!(steps.next().is_none());
+    }
 }
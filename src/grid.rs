@@ -0,0 +1,415 @@
+use std::fmt::Display;
+
+use crate::graph::{Graph, HeuristicGraph, WeightedGraph};
+
+/// The cost of a horizontal or vertical step.
+pub const STRAIGHT_COST: i64 = 1000;
+/// The cost of a diagonal step, √2 scaled like `STRAIGHT_COST`.
+pub const DIAGONAL_COST: i64 = 1414;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cell {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Cell {
+    #[must_use]
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// A step from a cell to one of its neighbours. North is towards y = 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const STRAIGHT: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+    pub const ALL: [Self; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    #[must_use]
+    pub const fn offset(self) -> (i64, i64) {
+        match self {
+            Self::North => (0, -1),
+            Self::NorthEast => (1, -1),
+            Self::East => (1, 0),
+            Self::SouthEast => (1, 1),
+            Self::South => (0, 1),
+            Self::SouthWest => (-1, 1),
+            Self::West => (-1, 0),
+            Self::NorthWest => (-1, -1),
+        }
+    }
+
+    /// The direction of a single step by (`dx`, `dy`), if there is one.
+    #[must_use]
+    pub const fn from_offset(dx: i64, dy: i64) -> Option<Self> {
+        match (dx, dy) {
+            (0, -1) => Some(Self::North),
+            (1, -1) => Some(Self::NorthEast),
+            (1, 0) => Some(Self::East),
+            (1, 1) => Some(Self::SouthEast),
+            (0, 1) => Some(Self::South),
+            (-1, 1) => Some(Self::SouthWest),
+            (-1, 0) => Some(Self::West),
+            (-1, -1) => Some(Self::NorthWest),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_diagonal(self) -> bool {
+        let (dx, dy) = self.offset();
+        dx != 0 && dy != 0
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::North => "N",
+            Self::NorthEast => "NE",
+            Self::East => "E",
+            Self::SouthEast => "SE",
+            Self::South => "S",
+            Self::SouthWest => "SW",
+            Self::West => "W",
+            Self::NorthWest => "NW",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    Four,
+    /// Diagonal steps are allowed only when both cells they pass between are
+    /// free, so paths never cut corners.
+    Eight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GridHeuristic {
+    /// The straight-step distance ignoring obstacles. Admissible only on
+    /// `Connectivity::Four` grids: with diagonal steps allowed it overestimates,
+    /// as one diagonal step costs less than the two straight ones it counts.
+    Manhattan,
+    /// The distance ignoring obstacles, taking diagonal steps where possible.
+    /// Admissible with either connectivity.
+    Octile,
+}
+
+/// A uniform-cost 2D grid with blocked cells.
+#[derive(Clone, Debug)]
+pub struct GridGraph {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    start: Cell,
+    goal: Cell,
+    connectivity: Connectivity,
+    heuristic: GridHeuristic,
+}
+
+impl GridGraph {
+    /// Creates an open, 8-connected grid with the octile heuristic.
+    #[must_use]
+    pub fn new(width: usize, height: usize, start: Cell, goal: Cell) -> Self {
+        Self {
+            width,
+            height,
+            blocked: vec![false; width * height],
+            start,
+            goal,
+            connectivity: Connectivity::Eight,
+            heuristic: GridHeuristic::Octile,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_heuristic(mut self, heuristic: GridHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub const fn start(&self) -> Cell {
+        self.start
+    }
+
+    #[must_use]
+    pub const fn goal(&self) -> Cell {
+        self.goal
+    }
+
+    #[must_use]
+    pub const fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    pub const fn set_start(&mut self, start: Cell) {
+        self.start = start;
+    }
+
+    pub const fn set_goal(&mut self, goal: Cell) {
+        self.goal = goal;
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `cell` is outside the grid.
+    pub fn set_blocked(&mut self, cell: Cell, blocked: bool) {
+        assert!(
+            cell.x < self.width && cell.y < self.height,
+            "cell {cell} is outside the grid"
+        );
+        self.blocked[cell.y * self.width + cell.x] = blocked;
+    }
+
+    /// Whether `cell` is outside the grid or blocked.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn is_blocked(&self, cell: Cell) -> bool {
+        !self.is_passable(cell.x as i64, cell.y as i64)
+    }
+
+    /// Whether the (possibly out-of-bounds) position is a free cell.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_possible_wrap
+    )]
+    pub fn is_passable(&self, x: i64, y: i64) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && !self.blocked[y as usize * self.width + x as usize]
+    }
+
+    /// The cell reached by stepping from `cell` in `direction`, if that step is allowed.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_possible_wrap
+    )]
+    pub fn step(&self, cell: Cell, direction: Direction) -> Option<Cell> {
+        if direction.is_diagonal() && self.connectivity == Connectivity::Four {
+            return None;
+        }
+        let (dx, dy) = direction.offset();
+        let (x, y) = (cell.x as i64, cell.y as i64);
+        let passable = self.is_passable(x + dx, y + dy)
+            && (!direction.is_diagonal()
+                || (self.is_passable(x + dx, y) && self.is_passable(x, y + dy)));
+        passable.then(|| Cell::new((x + dx) as usize, (y + dy) as usize))
+    }
+
+    const fn directions(&self) -> &'static [Direction] {
+        match self.connectivity {
+            Connectivity::Four => &Direction::STRAIGHT,
+            Connectivity::Eight => &Direction::ALL,
+        }
+    }
+}
+
+impl Graph for GridGraph {
+    type Node = Cell;
+    type Edge = Direction;
+
+    fn root(&self) -> Self::Node {
+        self.start
+    }
+
+    fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+        self.directions()
+            .iter()
+            .filter_map(|&d| self.step(node, d))
+            .collect()
+    }
+
+    fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+        self.directions()
+            .iter()
+            .copied()
+            .filter(|&d| self.step(node, d).is_some())
+            .collect()
+    }
+
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        self.directions()
+            .iter()
+            .filter_map(|&d| self.step(node, d).map(|cell| (d, cell)))
+            .collect()
+    }
+
+    fn for_each_child(&self, node: Self::Node, mut f: impl FnMut(Self::Node)) {
+        for &d in self.directions() {
+            if let Some(cell) = self.step(node, d) {
+                f(cell);
+            }
+        }
+    }
+
+    fn for_each_successor(&self, node: Self::Node, mut f: impl FnMut(Self::Edge, Self::Node)) {
+        for &d in self.directions() {
+            if let Some(cell) = self.step(node, d) {
+                f(d, cell);
+            }
+        }
+    }
+
+    fn is_goal(&self, node: Self::Node) -> bool {
+        node == self.goal
+    }
+}
+
+impl WeightedGraph for GridGraph {
+    fn edge_weight(&self, from: Self::Node, to: Self::Node) -> i64 {
+        if from.x != to.x && from.y != to.y {
+            DIAGONAL_COST
+        } else {
+            STRAIGHT_COST
+        }
+    }
+}
+
+impl HeuristicGraph for GridGraph {
+    #[allow(clippy::cast_possible_wrap)]
+    fn heuristic(&self, node: Self::Node) -> i64 {
+        let dx = node.x.abs_diff(self.goal.x) as i64;
+        let dy = node.y.abs_diff(self.goal.y) as i64;
+        match self.heuristic {
+            GridHeuristic::Manhattan => STRAIGHT_COST * (dx + dy),
+            GridHeuristic::Octile => {
+                STRAIGHT_COST * (dx.max(dy) - dx.min(dy)) + DIAGONAL_COST * dx.min(dy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heuristiccheck::HeuristicChecker;
+
+    /// A 4x3 grid with the cell at (1, 1) blocked.
+    fn pillar() -> GridGraph {
+        let mut grid = GridGraph::new(4, 3, Cell::new(0, 0), Cell::new(3, 2));
+        grid.set_blocked(Cell::new(1, 1), true);
+        grid
+    }
+
+    #[test]
+    fn passable() {
+        let grid = pillar();
+        assert!(grid.is_passable(0, 0));
+        assert!(grid.is_passable(3, 2));
+        assert!(!grid.is_passable(1, 1));
+        assert!(!grid.is_passable(-1, 0));
+        assert!(!grid.is_passable(0, -1));
+        assert!(!grid.is_passable(4, 0));
+        assert!(!grid.is_passable(0, 3));
+        assert!(grid.is_blocked(Cell::new(1, 1)));
+        assert!(grid.is_blocked(Cell::new(4, 2)));
+    }
+
+    #[test]
+    fn no_corner_cutting() {
+        let grid = pillar();
+        // a diagonal step is refused if either cell beside it is blocked, not
+        // just the cell it lands on.
+        assert_eq!(grid.step(Cell::new(0, 0), Direction::SouthEast), None);
+        assert_eq!(grid.step(Cell::new(2, 0), Direction::SouthWest), None);
+        assert_eq!(grid.step(Cell::new(0, 2), Direction::NorthEast), None);
+        assert_eq!(
+            grid.step(Cell::new(2, 0), Direction::SouthEast),
+            Some(Cell::new(3, 1))
+        );
+        assert_eq!(grid.step(Cell::new(0, 0), Direction::North), None);
+        assert_eq!(grid.step(Cell::new(0, 0), Direction::West), None);
+    }
+
+    #[test]
+    fn four_connected() {
+        let grid = pillar().with_connectivity(Connectivity::Four);
+        assert_eq!(grid.step(Cell::new(2, 0), Direction::SouthEast), None);
+        assert_eq!(
+            grid.successors(Cell::new(2, 1)),
+            &[
+                (Direction::North, Cell::new(2, 0)),
+                (Direction::East, Cell::new(3, 1)),
+                (Direction::South, Cell::new(2, 2)),
+            ]
+        );
+        let open = GridGraph::new(3, 3, Cell::new(1, 1), Cell::new(0, 0));
+        assert_eq!(open.children(Cell::new(1, 1)).len(), 8);
+        let open = open.with_connectivity(Connectivity::Four);
+        assert_eq!(open.children(Cell::new(1, 1)).len(), 4);
+    }
+
+    #[test]
+    fn heuristics() {
+        let grid = GridGraph::new(5, 5, Cell::new(0, 0), Cell::new(3, 1));
+        assert_eq!(
+            grid.heuristic(Cell::new(0, 0)),
+            2 * STRAIGHT_COST + DIAGONAL_COST
+        );
+        assert_eq!(grid.heuristic(grid.goal()), 0);
+        let manhattan = grid.with_heuristic(GridHeuristic::Manhattan);
+        assert_eq!(manhattan.heuristic(Cell::new(0, 0)), 4 * STRAIGHT_COST);
+
+        let checker = HeuristicChecker::new(1000);
+        let report = checker.check(&pillar(), Cell::new(0, 0));
+        assert!(report.complete && report.is_admissible() && report.is_consistent());
+        let four = pillar()
+            .with_connectivity(Connectivity::Four)
+            .with_heuristic(GridHeuristic::Manhattan);
+        assert!(checker.check(&four, Cell::new(0, 0)).is_admissible());
+        let eight = pillar().with_heuristic(GridHeuristic::Manhattan);
+        assert!(!checker.check(&eight, Cell::new(0, 0)).is_admissible());
+    }
+}
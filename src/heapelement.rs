@@ -1,11 +1,11 @@
 #[derive(Clone, Copy, Debug)]
-pub struct HeapElement<T> {
+pub struct HeapElement<T, C = i64> {
     pub node: T,
-    pub cost: i64,
+    pub cost: C,
 }
 
-impl<T> HeapElement<T> {
-    pub const fn new(node: T, cost: i64) -> Self {
+impl<T, C> HeapElement<T, C> {
+    pub const fn new(node: T, cost: C) -> Self {
         Self { node, cost }
     }
 
@@ -14,22 +14,21 @@ impl<T> HeapElement<T> {
     }
 }
 
-impl<T> PartialEq for HeapElement<T> {
+impl<T, C: Ord> PartialEq for HeapElement<T, C> {
     fn eq(&self, other: &Self) -> bool {
         self.cost == other.cost
     }
 }
 
-impl<T> Eq for HeapElement<T> {}
+impl<T, C: Ord> Eq for HeapElement<T, C> {}
 
-impl<T> PartialOrd for HeapElement<T> {
+impl<T, C: Ord> PartialOrd for HeapElement<T, C> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        // reverse order
-        Some(other.cost.cmp(&self.cost))
+        Some(self.cmp(other))
     }
 }
 
-impl<T> Ord for HeapElement<T> {
+impl<T, C: Ord> Ord for HeapElement<T, C> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // reverse order
         other.cost.cmp(&self.cost)
@@ -0,0 +1,244 @@
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use crate::graph::{HeuristicGraph, WeightedGraph};
+use crate::heapelement::HeapElement;
+
+pub const DEFAULT_MAX_EXAMPLES: usize = 10;
+
+/// A node whose heuristic exceeds its true cost to the nearest goal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overestimate<N> {
+    pub node: N,
+    pub heuristic: i64,
+    pub true_cost: i64,
+}
+
+/// An edge where h(from) > weight + h(to).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Inconsistency<N> {
+    pub from: N,
+    pub to: N,
+    pub weight: i64,
+    pub heuristic_from: i64,
+    pub heuristic_to: i64,
+}
+
+/// The findings of a `HeuristicChecker`. The examples are the first
+/// violations found, up to the checker's limit; the counts cover all of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeuristicReport<N> {
+    pub nodes_explored: usize,
+    pub edges_checked: usize,
+    /// Whether every node reachable from the root was explored.
+    pub complete: bool,
+    /// Nodes whose true cost couldn't be established, because a cheaper path
+    /// might run through nodes beyond the node limit.
+    pub unverified: usize,
+    pub overestimates: usize,
+    pub inconsistencies: usize,
+    pub overestimate_examples: Vec<Overestimate<N>>,
+    pub inconsistency_examples: Vec<Inconsistency<N>>,
+}
+
+impl<N> HeuristicReport<N> {
+    /// Whether no verified node was overestimated.
+    #[must_use]
+    pub const fn is_admissible(&self) -> bool {
+        self.overestimates == 0
+    }
+
+    #[must_use]
+    pub const fn is_consistent(&self) -> bool {
+        self.inconsistencies == 0
+    }
+}
+
+/// Checks a graph's heuristic for admissibility and consistency.
+///
+/// The graph is explored breadth-first up to a node limit, and true costs to
+/// the goal are found by a backward Dijkstra search over the explored edges.
+/// Edge weights must be non-negative.
+#[derive(Clone, Debug)]
+pub struct HeuristicChecker {
+    node_limit: usize,
+    max_examples: usize,
+}
+
+impl HeuristicChecker {
+    #[must_use]
+    pub const fn new(node_limit: usize) -> Self {
+        Self {
+            node_limit,
+            max_examples: DEFAULT_MAX_EXAMPLES,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_max_examples(mut self, max_examples: usize) -> Self {
+        self.max_examples = max_examples;
+        self
+    }
+
+    pub fn check<G: WeightedGraph + HeuristicGraph>(
+        &self,
+        graph: &G,
+        root: G::Node,
+    ) -> HeuristicReport<G::Node> {
+        let mut nodes = vec![root];
+        let mut index = HashMap::from([(root, 0)]);
+        let mut edges = Vec::new();
+        let mut expanded = vec![false];
+        let mut queue = VecDeque::from([0]);
+        while nodes.len() < self.node_limit {
+            let Some(u) = queue.pop_front() else {
+                break;
+            };
+            expanded[u] = true;
+            for (_, child, weight) in graph.weighted_successors(nodes[u]) {
+                let v = *index.entry(child).or_insert_with(|| {
+                    nodes.push(child);
+                    expanded.push(false);
+                    queue.push_back(nodes.len() - 1);
+                    nodes.len() - 1
+                });
+                edges.push((u, v, weight));
+            }
+        }
+        let mut reverse = vec![Vec::new(); nodes.len()];
+        for &(u, v, w) in &edges {
+            reverse[v].push((u, w));
+        }
+
+        // true costs, over the explored edges, from every goal backwards.
+        let mut true_costs = vec![i64::MAX; nodes.len()];
+        let mut heap = BinaryHeap::new();
+        for (i, &node) in nodes.iter().enumerate() {
+            if graph.is_goal(node) {
+                true_costs[i] = 0;
+                heap.push(HeapElement::new(i, 0));
+            }
+        }
+        while let Some(HeapElement { node: v, cost }) = heap.pop() {
+            if cost > true_costs[v] {
+                continue;
+            }
+            for &(u, w) in &reverse[v] {
+                if cost + w < true_costs[u] {
+                    true_costs[u] = cost + w;
+                    heap.push(HeapElement::new(u, cost + w));
+                }
+            }
+        }
+
+        // a node's true cost is only known if it can't reach an unexplored
+        // node. Paths that carry on past a goal never need to.
+        let mut unverified = vec![false; nodes.len()];
+        let mut stack = (0..nodes.len())
+            .filter(|&i| !expanded[i] && !graph.is_goal(nodes[i]))
+            .collect::<Vec<_>>();
+        for &i in &stack {
+            unverified[i] = true;
+        }
+        while let Some(v) = stack.pop() {
+            for &(u, _) in &reverse[v] {
+                if !unverified[u] && !graph.is_goal(nodes[u]) {
+                    unverified[u] = true;
+                    stack.push(u);
+                }
+            }
+        }
+
+        let heuristics = nodes
+            .iter()
+            .map(|&n| graph.heuristic(n))
+            .collect::<Vec<_>>();
+        let mut report = HeuristicReport {
+            nodes_explored: nodes.len(),
+            edges_checked: edges.len(),
+            complete: expanded.iter().all(|&e| e),
+            unverified: unverified.iter().filter(|&&u| u).count(),
+            overestimates: 0,
+            inconsistencies: 0,
+            overestimate_examples: Vec::new(),
+            inconsistency_examples: Vec::new(),
+        };
+        for (i, &node) in nodes.iter().enumerate() {
+            if !unverified[i] && heuristics[i] > true_costs[i] {
+                report.overestimates += 1;
+                if report.overestimate_examples.len() < self.max_examples {
+                    report.overestimate_examples.push(Overestimate {
+                        node,
+                        heuristic: heuristics[i],
+                        true_cost: true_costs[i],
+                    });
+                }
+            }
+        }
+        for &(u, v, w) in &edges {
+            if heuristics[u] > heuristics[v].saturating_add(w) {
+                report.inconsistencies += 1;
+                if report.inconsistency_examples.len() < self.max_examples {
+                    report.inconsistency_examples.push(Inconsistency {
+                        from: nodes[u],
+                        to: nodes[v],
+                        weight: w,
+                        heuristic_from: heuristics[u],
+                        heuristic_to: heuristics[v],
+                    });
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+    use crate::heuristics::ScaledHeuristic;
+    use crate::tilepuzzle::{SlidingPuzzle, TileHeuristic};
+
+    #[test]
+    fn example_graph() {
+        // h is 1 everywhere, including at the goal.
+        let graph = get_example_graph();
+        let report = HeuristicChecker::new(100).check(&graph, graph.root());
+        assert!(report.complete);
+        assert_eq!(report.nodes_explored, 9);
+        assert_eq!(report.unverified, 0);
+        assert!(report.is_consistent());
+        assert_eq!(
+            report.overestimate_examples,
+            [Overestimate {
+                node: ExampleNode::new(7),
+                heuristic: 1,
+                true_cost: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn puzzle() {
+        let puzzle = SlidingPuzzle::random(3, 2, 5).with_heuristic(TileHeuristic::Manhattan);
+        let report = HeuristicChecker::new(1000).check(&puzzle, puzzle.root());
+        assert!(report.complete);
+        assert_eq!(report.nodes_explored, 360);
+        assert!(report.is_admissible() && report.is_consistent());
+
+        let scaled = ScaledHeuristic::new(puzzle.clone(), 3.0);
+        let report = HeuristicChecker::new(1000)
+            .with_max_examples(2)
+            .check(&scaled, scaled.root());
+        assert!(!report.is_admissible() && !report.is_consistent());
+        assert_eq!(report.inconsistency_examples.len(), 2);
+        let example = report.inconsistency_examples[0];
+        assert!(example.heuristic_from > example.weight + example.heuristic_to);
+
+        let partial = HeuristicChecker::new(50).check(&puzzle, puzzle.root());
+        assert!(!partial.complete);
+        assert!(partial.unverified > 0);
+        assert!(partial.is_admissible());
+    }
+}
@@ -0,0 +1,383 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::cost::Cost;
+use crate::graph::{Graph, HeuristicGraph, WeightedGraph};
+
+/// A heuristic over the nodes of `G`, such as `G::heuristic` or a closure.
+///
+/// The adapters here give an existing graph a different heuristic, so several
+/// can be compared on the same domain without a newtype for each.
+pub type HeuristicFn<G, C = i64> = Box<dyn Fn(&G, <G as Graph>::Node) -> C>;
+
+/// Implements `Graph` and `WeightedGraph` for an adapter by forwarding to
+/// its `graph` field. Parameters after `G` are passed through with their
+/// bounds.
+macro_rules! delegate_graph {
+    ($adapter:ident<G $(, $param:ident: $param_bound:path)*>, $bound:path) => {
+        impl<G: $bound $(, $param: $param_bound)*> Graph for $adapter<G $(, $param)*> {
+            type Node = G::Node;
+            type Edge = G::Edge;
+
+            fn root(&self) -> Self::Node {
+                self.graph.root()
+            }
+
+            fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+                self.graph.children(node)
+            }
+
+            fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+                self.graph.edges(node)
+            }
+
+            fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+                self.graph.successors(node)
+            }
+
+            fn for_each_child(&self, node: Self::Node, f: impl FnMut(Self::Node)) {
+                self.graph.for_each_child(node, f);
+            }
+
+            fn for_each_successor(&self, node: Self::Node, f: impl FnMut(Self::Edge, Self::Node)) {
+                self.graph.for_each_successor(node, f);
+            }
+
+            fn is_goal(&self, node: Self::Node) -> bool {
+                self.graph.is_goal(node)
+            }
+        }
+
+        impl<G: $bound + WeightedGraph<W>, W: Cost $(, $param: $param_bound)*> WeightedGraph<W>
+            for $adapter<G $(, $param)*>
+        {
+            fn edge_weight(&self, from: Self::Node, to: Self::Node) -> W {
+                self.graph.edge_weight(from, to)
+            }
+
+            fn weighted_successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node, W)> {
+                self.graph.weighted_successors(node)
+            }
+
+            fn for_each_weighted_successor(
+                &self,
+                node: Self::Node,
+                f: impl FnMut(Self::Edge, Self::Node, W),
+            ) {
+                self.graph.for_each_weighted_successor(node, f);
+            }
+        }
+
+        impl<G: $bound $(, $param: $param_bound)*> $adapter<G $(, $param)*> {
+            #[must_use]
+            pub const fn graph(&self) -> &G {
+                &self.graph
+            }
+
+            #[must_use]
+            pub fn into_inner(self) -> G {
+                self.graph
+            }
+        }
+    };
+}
+
+/// Multiplies `heuristic` by `factor`, rounding down and keeping infinite
+/// estimates infinite.
+#[allow(clippy::float_cmp)]
+fn scale<C: Cost>(heuristic: C, factor: f64) -> C {
+    if heuristic == C::MAX || factor == 1.0 {
+        heuristic
+    } else {
+        heuristic.scale(factor)
+    }
+}
+
+/// Estimates every node at zero, so `AStar` behaves like `Dijkstra`.
+#[derive(Clone, Debug)]
+pub struct ZeroHeuristic<G: Graph, C: Cost = i64> {
+    graph: G,
+    cost: PhantomData<C>,
+}
+
+impl<G: Graph, C: Cost> ZeroHeuristic<G, C> {
+    #[must_use]
+    pub const fn new(graph: G) -> Self {
+        Self {
+            graph,
+            cost: PhantomData,
+        }
+    }
+}
+
+delegate_graph!(ZeroHeuristic<G, C: Cost>, Graph);
+
+impl<G: Graph, C: Cost> HeuristicGraph<C> for ZeroHeuristic<G, C> {
+    fn heuristic(&self, _node: Self::Node) -> C {
+        C::ZERO
+    }
+}
+
+/// The largest of several heuristics. The result is admissible if they all
+/// are.
+pub struct MaxHeuristic<G: Graph, C: Cost = i64> {
+    graph: G,
+    heuristics: Vec<HeuristicFn<G, C>>,
+}
+
+impl<G: Graph + Debug, C: Cost> Debug for MaxHeuristic<G, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MaxHeuristic({:?}, {} heuristics)",
+            self.graph,
+            self.heuristics.len()
+        )
+    }
+}
+
+impl<G: Graph, C: Cost> MaxHeuristic<G, C> {
+    #[must_use]
+    pub const fn new(graph: G, heuristics: Vec<HeuristicFn<G, C>>) -> Self {
+        Self { graph, heuristics }
+    }
+}
+
+delegate_graph!(MaxHeuristic<G, C: Cost>, Graph);
+
+impl<G: Graph, C: Cost> HeuristicGraph<C> for MaxHeuristic<G, C> {
+    fn heuristic(&self, node: Self::Node) -> C {
+        self.heuristics
+            .iter()
+            .map(|h| h(&self.graph, node))
+            .max()
+            .unwrap_or(C::ZERO)
+    }
+}
+
+/// A weighted sum of heuristics, each term rounded down. The result is
+/// admissible if every heuristic is and the weights sum to at most 1.
+pub struct WeightedSumHeuristic<G: Graph, C: Cost = i64> {
+    graph: G,
+    terms: Vec<(f64, HeuristicFn<G, C>)>,
+}
+
+impl<G: Graph + Debug, C: Cost> Debug for WeightedSumHeuristic<G, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let weights = self.terms.iter().map(|&(w, _)| w).collect::<Vec<_>>();
+        write!(f, "WeightedSumHeuristic({:?}, {weights:?})", self.graph)
+    }
+}
+
+impl<G: Graph, C: Cost> WeightedSumHeuristic<G, C> {
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite.
+    #[must_use]
+    pub fn new(graph: G, terms: Vec<(f64, HeuristicFn<G, C>)>) -> Self {
+        assert!(
+            terms.iter().all(|&(w, _)| w.is_finite() && w >= 0.0),
+            "heuristic weights must be finite and non-negative"
+        );
+        Self { graph, terms }
+    }
+}
+
+delegate_graph!(WeightedSumHeuristic<G, C: Cost>, Graph);
+
+impl<G: Graph, C: Cost> HeuristicGraph<C> for WeightedSumHeuristic<G, C> {
+    fn heuristic(&self, node: Self::Node) -> C {
+        self.terms
+            .iter()
+            .filter(|&&(w, _)| w > 0.0)
+            .map(|(w, h)| scale(h(&self.graph, node), *w))
+            .fold(C::ZERO, |sum, term| sum.checked_add(term).unwrap_or(C::MAX))
+    }
+}
+
+/// The graph's own heuristic multiplied by a constant factor and rounded
+/// down.
+#[derive(Clone, Debug)]
+pub struct ScaledHeuristic<G: Graph> {
+    graph: G,
+    factor: f64,
+}
+
+impl<G: Graph> ScaledHeuristic<G> {
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or not finite.
+    #[must_use]
+    pub fn new(graph: G, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 0.0,
+            "heuristic factor must be finite and non-negative"
+        );
+        Self { graph, factor }
+    }
+
+    #[must_use]
+    pub const fn factor(&self) -> f64 {
+        self.factor
+    }
+}
+
+delegate_graph!(ScaledHeuristic<G>, Graph);
+
+impl<G: HeuristicGraph<C>, C: Cost> HeuristicGraph<C> for ScaledHeuristic<G> {
+    fn heuristic(&self, node: Self::Node) -> C {
+        scale(self.graph.heuristic(node), self.factor)
+    }
+}
+
+/// Remembers the graph's heuristic for every node it is asked about, for
+/// heuristics that are expensive to compute.
+#[derive(Clone, Debug)]
+pub struct CachedHeuristic<G: HeuristicGraph<C>, C: Cost = i64> {
+    graph: G,
+    cache: RefCell<HashMap<G::Node, C>>,
+}
+
+impl<G: HeuristicGraph<C>, C: Cost> CachedHeuristic<G, C> {
+    #[must_use]
+    pub fn new(graph: G) -> Self {
+        Self {
+            graph,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The number of nodes with a remembered heuristic.
+    #[must_use]
+    pub fn cached(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+delegate_graph!(CachedHeuristic<G, C: Cost>, HeuristicGraph<C>);
+
+impl<G: HeuristicGraph<C>, C: Cost> HeuristicGraph<C> for CachedHeuristic<G, C> {
+    fn heuristic(&self, node: Self::Node) -> C {
+        if let Some(&h) = self.cache.borrow().get(&node) {
+            return h;
+        }
+        let h = self.graph.heuristic(node);
+        self.cache.borrow_mut().insert(node, h);
+        h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::cost::TotalF64;
+    use crate::dijkstra::Dijkstra;
+    use crate::graphsearcher::GraphSearcher;
+    use crate::patterndb::PdbHeuristic;
+    use crate::tilepuzzle::{SlidingPuzzle, TileHeuristic};
+
+    fn cost<G: WeightedGraph + HeuristicGraph>(graph: &G) -> (i64, usize) {
+        let result = AStar::new().search_tracked(graph, graph.root());
+        (result.cost.unwrap(), result.stats.expanded)
+    }
+
+    #[test]
+    fn combinators() {
+        let puzzle = SlidingPuzzle::random(3, 3, 3).with_heuristic(TileHeuristic::Manhattan);
+        let start = puzzle.start();
+        let mut dijkstra = Dijkstra::new();
+        dijkstra.search_tracked(&puzzle, start);
+        let optimal = puzzle.path_cost(&dijkstra.path().unwrap());
+
+        let zero = ZeroHeuristic::new(puzzle.clone());
+        assert_eq!(zero.heuristic(start), 0);
+        assert_eq!(cost(&zero).0, optimal);
+
+        let max = MaxHeuristic::new(
+            puzzle.clone(),
+            vec![
+                Box::new(SlidingPuzzle::manhattan),
+                Box::new(SlidingPuzzle::linear_conflict),
+            ],
+        );
+        assert_eq!(max.heuristic(start), puzzle.linear_conflict(start));
+        assert_eq!(cost(&max).0, optimal);
+        assert!(cost(&max).1 <= cost(&puzzle).1);
+        assert!(cost(&puzzle).1 <= cost(&zero).1);
+
+        let sum = WeightedSumHeuristic::new(
+            puzzle.clone(),
+            vec![
+                (0.5, Box::new(SlidingPuzzle::manhattan)),
+                (0.5, Box::new(|_, _| 7)),
+            ],
+        );
+        assert_eq!(sum.heuristic(start), puzzle.manhattan(start) / 2 + 3);
+
+        let scaled = ScaledHeuristic::new(puzzle.clone(), 2.5);
+        assert_eq!(scaled.heuristic(start), puzzle.manhattan(start) * 5 / 2);
+        assert!(cost(&scaled).0 >= optimal);
+        assert_eq!(scaled.into_inner().start(), start);
+    }
+
+    #[test]
+    fn cached() {
+        let puzzle = SlidingPuzzle::random(3, 3, 4);
+        let cached = CachedHeuristic::new(puzzle.clone());
+        let (found, _) = cost(&cached);
+        assert_eq!(found, cost(&puzzle).0);
+        assert!(cached.cached() > 0);
+        let h = cached.heuristic(puzzle.start());
+        let remembered = cached.cached();
+        assert_eq!(h, puzzle.heuristic(puzzle.start()));
+        assert_eq!(cached.heuristic(puzzle.start()), h);
+        assert_eq!(cached.cached(), remembered);
+        cached.clear_cache();
+        assert_eq!(cached.cached(), 0);
+    }
+
+    #[test]
+    fn stateful() {
+        let puzzle = SlidingPuzzle::random(3, 3, 5);
+        let pdb = PdbHeuristic::additive(puzzle.clone(), &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        let start = puzzle.start();
+        let from_pdb = pdb.heuristic(start);
+        let max = MaxHeuristic::new(
+            puzzle.clone(),
+            vec![
+                Box::new(move |_, node| pdb.heuristic(node)),
+                Box::new(SlidingPuzzle::linear_conflict),
+            ],
+        );
+        assert_eq!(
+            max.heuristic(start),
+            from_pdb.max(puzzle.linear_conflict(start))
+        );
+        assert_eq!(cost(&max).0, cost(&puzzle).0);
+    }
+
+    #[test]
+    fn generic_costs() {
+        let puzzle = SlidingPuzzle::random(3, 3, 6);
+        let start = puzzle.start();
+        let halved = |graph: &SlidingPuzzle, node| TotalF64(graph.heuristic(node).to_f64() / 2.0);
+        let sum = WeightedSumHeuristic::new(
+            puzzle.clone(),
+            vec![
+                (1.0, Box::new(halved)),
+                (0.25, Box::new(|_, _| TotalF64(2.0))),
+            ],
+        );
+        let expected = puzzle.heuristic(start).to_f64() / 2.0 + 0.5;
+        assert_eq!(sum.heuristic(start), TotalF64(expected));
+        let zero: TotalF64 = ZeroHeuristic::new(puzzle).heuristic(start);
+        assert_eq!(zero, TotalF64::ZERO);
+    }
+}
@@ -0,0 +1,433 @@
+use std::fmt::Debug;
+
+use crate::budget::DepthLimit;
+use crate::cost::CostOverflow;
+use crate::graph::{HeuristicGraph, WeightedGraph};
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+/// Iterative-deepening A*.
+///
+/// Each iteration is a depth-first search bounded by f = g + h, and the next
+/// bound is the smallest f that exceeded the current one. Only the current
+/// path is kept in memory, so `is_visited` reports whether a node lies on the
+/// path of the most recent search.
+pub struct IdaStar<G: WeightedGraph + HeuristicGraph> {
+    path: Vec<G::Node>,
+    /// the edges between the nodes of `path`.
+    edges: Vec<G::Edge>,
+    /// the expanded nodes on the path, with their unvisited children.
+    frames: Vec<Frame<G::Node, G::Edge>>,
+    /// a child entered but not yet checked against the bound, with its cost
+    /// and f-value.
+    pending: Option<(G::Node, i64, i64)>,
+    /// the root while the search is running.
+    root: Option<G::Node>,
+    bound: i64,
+    next_bound: i64,
+    found: bool,
+    /// the cost of `path` once the goal is found.
+    cost: i64,
+    /// the edge whose cost overflowed, ending the search.
+    overflow: Option<CostOverflow<G::Node>>,
+    iteration_counts: Vec<usize>,
+    depth_limit: DepthLimit,
+}
+
+struct Frame<N, E> {
+    node: N,
+    cost: i64,
+    children: std::vec::IntoIter<(E, N, i64)>,
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Debug for IdaStar<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdaStar")
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> IdaStar<G> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            path: Vec::new(),
+            edges: Vec::new(),
+            frames: Vec::new(),
+            pending: None,
+            root: None,
+            bound: 0,
+            next_bound: i64::MAX,
+            found: false,
+            cost: 0,
+            overflow: None,
+            iteration_counts: Vec::new(),
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn iterations(&self) -> usize {
+        self.iteration_counts.len()
+    }
+
+    /// The number of nodes expanded in each iteration, in order.
+    #[must_use]
+    pub fn iteration_nodes(&self) -> &[usize] {
+        &self.iteration_counts
+    }
+
+    /// Finds a cheapest goal from `root` without keeping any search state.
+    ///
+    /// # Errors
+    ///
+    /// Returns the offending edge if the cost of a route overflows.
+    pub fn try_search(graph: &G, root: G::Node) -> Result<Option<G::Node>, CostOverflow<G::Node>> {
+        let mut path = Vec::new();
+        let mut bound = graph.heuristic(root);
+        loop {
+            path.clear();
+            path.push(root);
+            match Self::bounded_search(graph, root, 0, bound, bound, &mut path)? {
+                Ok(node) => return Ok(Some(node)),
+                Err(i64::MAX) => return Ok(None),
+                Err(next_bound) => bound = next_bound,
+            }
+        }
+    }
+
+    /// The cost of `child` reached from `node` by an edge of `weight`, and
+    /// its f-value, which is `i64::MAX` if no goal can be reached from it.
+    fn price(
+        graph: &G,
+        node: G::Node,
+        child: G::Node,
+        cost: i64,
+        weight: i64,
+    ) -> Result<(i64, i64), CostOverflow<G::Node>> {
+        let overflow = CostOverflow {
+            from: node,
+            to: child,
+        };
+        let cost_to_child = cost.checked_add(weight).ok_or(overflow)?;
+        let heuristic = graph.heuristic(child);
+        if heuristic == i64::MAX {
+            return Ok((cost_to_child, i64::MAX));
+        }
+        let f = cost_to_child.checked_add(heuristic).ok_or(overflow)?;
+        Ok((cost_to_child, f))
+    }
+
+    /// Returns the goal if one is found within `bound`, otherwise the smallest
+    /// f-value that exceeded it (`i64::MAX` if nothing did), or the edge whose
+    /// cost overflowed.
+    fn bounded_search(
+        graph: &G,
+        node: G::Node,
+        cost: i64,
+        f: i64,
+        bound: i64,
+        path: &mut Vec<G::Node>,
+    ) -> Result<Result<G::Node, i64>, CostOverflow<G::Node>> {
+        // an infinite estimate is a dead end, whatever the bound.
+        if f > bound || f == i64::MAX {
+            return Ok(Err(f));
+        }
+        if graph.is_goal(node) {
+            return Ok(Ok(node));
+        }
+        let mut next_bound = i64::MAX;
+        for (_, child, weight) in graph.weighted_successors(node) {
+            if path.contains(&child) {
+                continue;
+            }
+            let (cost_to_child, f_child) = Self::price(graph, node, child, cost, weight)?;
+            path.push(child);
+            match Self::bounded_search(graph, child, cost_to_child, f_child, bound, path)? {
+                Ok(goal) => return Ok(Ok(goal)),
+                Err(f) => next_bound = std::cmp::min(next_bound, f),
+            }
+            path.pop();
+        }
+        Ok(Err(next_bound))
+    }
+
+    fn mark_expanded(&mut self) {
+        if let Some(count) = self.iteration_counts.last_mut() {
+            *count += 1;
+        }
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Default for IdaStar<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> StepSearcher<G> for IdaStar<G> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.found = false;
+        self.overflow = None;
+        self.iteration_counts = vec![0];
+        self.bound = graph.heuristic(root);
+        self.next_bound = i64::MAX;
+        self.path = vec![root];
+        self.edges.clear();
+        self.frames.clear();
+        self.pending = Some((root, 0, self.bound));
+        self.root = Some(root);
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        loop {
+            if let Some((node, cost, f)) = self.pending.take() {
+                if f > self.bound || f == i64::MAX {
+                    observer.on_prune(node);
+                    self.next_bound = std::cmp::min(self.next_bound, f);
+                    self.path.pop();
+                    self.edges.pop();
+                    continue;
+                }
+                if graph.is_goal(node) {
+                    observer.on_goal(node);
+                    self.found = true;
+                    self.cost = cost;
+                    self.frames.clear();
+                    self.root = None;
+                    return None;
+                }
+                if !self.depth_limit.expands(graph, node, self.path.len() - 1) {
+                    observer.on_prune(node);
+                    self.path.pop();
+                    self.edges.pop();
+                    continue;
+                }
+                self.mark_expanded();
+                observer.on_expand(node);
+                let successors = graph.weighted_successors(node);
+                for &(_, child, _) in &successors {
+                    observer.on_generate(node, child);
+                }
+                self.frames.push(Frame {
+                    node,
+                    cost,
+                    children: successors.into_iter(),
+                });
+                return Some(SearchStep {
+                    node,
+                    children: Vec::new(),
+                    frontier: self.frames.iter().map(|frame| frame.children.len()).sum(),
+                    f: Some(f),
+                });
+            }
+            if let Some(frame) = self.frames.last_mut() {
+                if let Some((edge, child, weight)) = frame.children.next() {
+                    if self.path.contains(&child) {
+                        observer.on_duplicate(child);
+                        continue;
+                    }
+                    match Self::price(graph, frame.node, child, frame.cost, weight) {
+                        Ok((cost_to_child, f)) => {
+                            self.path.push(child);
+                            self.edges.push(edge);
+                            self.pending = Some((child, cost_to_child, f));
+                        }
+                        Err(overflow) => {
+                            self.overflow = Some(overflow);
+                            self.frames.clear();
+                            self.root = None;
+                            return None;
+                        }
+                    }
+                } else {
+                    self.frames.pop();
+                    self.path.pop();
+                    self.edges.pop();
+                }
+                continue;
+            }
+            // the iteration is over, start the next one with the smallest f
+            // that exceeded this bound.
+            let root = self.root?;
+            if self.next_bound == i64::MAX {
+                self.root = None;
+                return None;
+            }
+            self.bound = std::mem::replace(&mut self.next_bound, i64::MAX);
+            self.path = vec![root];
+            self.edges.clear();
+            self.iteration_counts.push(0);
+            self.pending = Some((root, 0, graph.heuristic(root)));
+        }
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        if self.found {
+            self.path.last().copied()
+        } else {
+            None
+        }
+    }
+
+    fn solution_cost(&self, _graph: &G) -> Option<i64> {
+        self.found.then_some(self.cost)
+    }
+
+    fn cost_overflow(&self) -> Option<CostOverflow<G::Node>> {
+        self.overflow
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> GraphSearcher<G> for IdaStar<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    /// Gives `None` on overflow as well; use `try_search` to tell the two
+    /// apart.
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::try_search(graph, root).ok().flatten()
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.path.contains(&node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        if self.found {
+            Some(self.path.clone())
+        } else {
+            None
+        }
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        if self.found {
+            Some(self.edges.clone())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::SearchOutcome;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+
+    /// A path 0 -> 1 -> ... with the given edge weights, ending at the goal.
+    struct Chain {
+        weights: Vec<i64>,
+    }
+
+    impl Graph for Chain {
+        type Node = usize;
+        type Edge = usize;
+
+        fn root(&self) -> Self::Node {
+            0
+        }
+
+        fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+            self.successors(node).into_iter().map(|(_, n)| n).collect()
+        }
+
+        fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+            self.successors(node).into_iter().map(|(e, _)| e).collect()
+        }
+
+        fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+            if node < self.weights.len() {
+                vec![(node, node + 1)]
+            } else {
+                Vec::new()
+            }
+        }
+
+        fn is_goal(&self, node: Self::Node) -> bool {
+            node == self.weights.len()
+        }
+    }
+
+    impl WeightedGraph for Chain {
+        fn edge_weight(&self, from: Self::Node, _to: Self::Node) -> i64 {
+            self.weights[from]
+        }
+    }
+
+    impl HeuristicGraph for Chain {
+        fn heuristic(&self, _node: Self::Node) -> i64 {
+            0
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let graph = get_example_graph();
+        let mut searcher = IdaStar::new();
+        let result = searcher.search_tracked(&graph, graph.root());
+        assert!(graph.is_goal(result.goal().unwrap()));
+        assert_eq!(result.cost, Some(3));
+        assert_eq!(
+            searcher.path().unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(3),
+                ExampleNode::new(6),
+                ExampleNode::new(7)
+            ]
+        );
+        assert_eq!(searcher.iterations(), searcher.iteration_nodes().len());
+        assert_eq!(
+            searcher.iteration_nodes().iter().sum::<usize>(),
+            result.stats.expanded
+        );
+    }
+
+    #[test]
+    fn no_solution() {
+        let graph = get_example_graph();
+        let mut searcher = IdaStar::new();
+        assert!(searcher
+            .search_tracked(&graph, ExampleNode::new(10))
+            .goal()
+            .is_none());
+        assert!(searcher.path().is_none());
+        assert!(IdaStar::search(&graph, ExampleNode::new(10)).is_none());
+    }
+
+    #[test]
+    fn extreme_weights() {
+        let graph = Chain {
+            weights: vec![i64::MAX - 2, 1],
+        };
+        let result = IdaStar::new().search_tracked(&graph, 0);
+        assert_eq!(result.cost, Some(i64::MAX - 1));
+
+        let graph = Chain {
+            weights: vec![i64::MAX - 1, 5],
+        };
+        let result = IdaStar::new().search_tracked(&graph, 0);
+        let overflow = CostOverflow { from: 1, to: 2 };
+        assert_eq!(result.outcome, SearchOutcome::Overflow(overflow));
+        assert!(result.path.is_none());
+        assert_eq!(IdaStar::search(&graph, 0), None);
+        assert_eq!(IdaStar::try_search(&graph, 0), Err(overflow));
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::Debug;
+
+use crate::budget::DepthLimit;
+use crate::graph::{Graph, HeuristicGraph};
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::grid::{Cell, Connectivity, Direction, GridGraph, DIAGONAL_COST, STRAIGHT_COST};
+use crate::heapelement::HeapElement;
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+/// Jump Point Search over a `GridGraph`.
+///
+/// Runs A* over jump points only, skipping the cells in between whenever
+/// every optimal path through them is symmetric to one that is kept. On
+/// 4-connected grids there's no diagonal symmetry to prune, so every
+/// neighbour is a successor, as in plain A*.
+pub struct JumpPointSearch {
+    distances: HashMap<Cell, i64>,
+    parents: HashMap<Cell, Cell>,
+    closed: HashSet<Cell>,
+    frontier: BinaryHeap<HeapElement<Cell>>,
+    solution: Option<Cell>,
+    depth_limit: DepthLimit,
+}
+
+impl Debug for JumpPointSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JumpPointSearch")
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn signed(cell: Cell) -> (i64, i64) {
+    (cell.x as i64, cell.y as i64)
+}
+
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+const fn unsigned(x: i64, y: i64) -> Cell {
+    Cell::new(x as usize, y as usize)
+}
+
+/// The cost of the straight or diagonal line between two jump points.
+#[allow(clippy::cast_possible_wrap)]
+const fn segment_cost(from: Cell, to: Cell) -> i64 {
+    let dx = from.x.abs_diff(to.x) as i64;
+    let dy = from.y.abs_diff(to.y) as i64;
+    if dx != 0 && dy != 0 {
+        DIAGONAL_COST * dx
+    } else {
+        STRAIGHT_COST * (dx + dy)
+    }
+}
+
+/// The neighbours of `cell` that can't be reached more cheaply through its
+/// parent, given the direction of travel.
+fn pruned_neighbours(graph: &GridGraph, cell: Cell, parent: Option<Cell>) -> Vec<Cell> {
+    let Some(parent) = parent else {
+        return graph.children(cell);
+    };
+    let (x, y) = signed(cell);
+    let (px, py) = signed(parent);
+    let (dx, dy) = ((x - px).signum(), (y - py).signum());
+    let free = |x, y| graph.is_passable(x, y);
+    let mut neighbours = Vec::new();
+    if dx != 0 && dy != 0 {
+        if free(x, y + dy) {
+            neighbours.push((x, y + dy));
+        }
+        if free(x + dx, y) {
+            neighbours.push((x + dx, y));
+        }
+        if free(x, y + dy) && free(x + dx, y) && free(x + dx, y + dy) {
+            neighbours.push((x + dx, y + dy));
+        }
+    } else {
+        // sideways offsets, perpendicular to the direction of travel.
+        let (sx, sy) = (dy.abs(), dx.abs());
+        let ahead = free(x + dx, y + dy);
+        for side in [1, -1] {
+            let (ox, oy) = (sx * side, sy * side);
+            if free(x + ox, y + oy) {
+                if ahead && free(x + dx + ox, y + dy + oy) {
+                    neighbours.push((x + dx + ox, y + dy + oy));
+                }
+                neighbours.push((x + ox, y + oy));
+            }
+        }
+        if ahead {
+            neighbours.push((x + dx, y + dy));
+        }
+    }
+    neighbours
+        .into_iter()
+        .map(|(x, y)| unsigned(x, y))
+        .collect()
+}
+
+/// Steps from `from` in direction (`dx`, `dy`) until reaching a jump point,
+/// or returns `None` if the way is blocked first.
+fn jump(graph: &GridGraph, from: Cell, dx: i64, dy: i64) -> Option<Cell> {
+    let free = |x, y| graph.is_passable(x, y);
+    let (mut x, mut y) = signed(from);
+    x += dx;
+    y += dy;
+    loop {
+        if !free(x, y) {
+            return None;
+        }
+        let cell = unsigned(x, y);
+        if graph.is_goal(cell) {
+            return Some(cell);
+        }
+        if dx != 0 && dy != 0 {
+            if jump(graph, cell, dx, 0).is_some() || jump(graph, cell, 0, dy).is_some() {
+                return Some(cell);
+            }
+        } else if dx != 0 {
+            if (free(x, y - 1) && !free(x - dx, y - 1)) || (free(x, y + 1) && !free(x - dx, y + 1))
+            {
+                return Some(cell);
+            }
+        } else if (free(x - 1, y) && !free(x - 1, y - dy))
+            || (free(x + 1, y) && !free(x + 1, y - dy))
+        {
+            return Some(cell);
+        }
+        if !(free(x + dx, y) && free(x, y + dy)) {
+            return None;
+        }
+        x += dx;
+        y += dy;
+    }
+}
+
+impl JumpPointSearch {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            distances: HashMap::new(),
+            parents: HashMap::new(),
+            closed: HashSet::new(),
+            frontier: BinaryHeap::new(),
+            solution: None,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn distances(&self) -> &HashMap<Cell, i64> {
+        &self.distances
+    }
+
+    /// The jump points on the route to the solution, from the root.
+    #[must_use]
+    pub fn jump_points(&self) -> Option<Vec<Cell>> {
+        let solution = self.solution?;
+        let mut path = Vec::new();
+        let mut n = solution;
+        path.push(n);
+        while let Some(&parent) = self.parents.get(&n) {
+            path.push(parent);
+            n = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn successor(graph: &GridGraph, from: Cell, neighbour: Cell) -> Option<Cell> {
+        if graph.connectivity() == Connectivity::Four {
+            return Some(neighbour);
+        }
+        let (x, y) = signed(from);
+        let (nx, ny) = signed(neighbour);
+        jump(graph, from, nx - x, ny - y)
+    }
+}
+
+impl Default for JumpPointSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Each step expands one jump point, and its children are the jump points
+/// it leads to.
+impl StepSearcher<GridGraph> for JumpPointSearch {
+    fn start(&mut self, graph: &GridGraph, root: Cell) {
+        self.depth_limit.reset();
+        self.distances.clear();
+        self.parents.clear();
+        self.closed.clear();
+        self.frontier.clear();
+        self.solution = None;
+
+        self.frontier
+            .push(HeapElement::new(root, graph.heuristic(root)));
+
+        self.distances.insert(root, 0);
+    }
+
+    fn step_observed<O: SearchObserver<GridGraph>>(
+        &mut self,
+        graph: &GridGraph,
+        observer: &mut O,
+    ) -> Option<SearchStep<Cell>> {
+        let (node, key) = loop {
+            let HeapElement { node, cost } = self.frontier.pop()?;
+            if graph.is_goal(node) {
+                observer.on_goal(node);
+                self.solution = Some(node);
+                self.frontier.clear();
+                return None;
+            }
+            if !self.closed.insert(node) {
+                continue;
+            }
+            // the depth is counted in jumps rather than cells.
+            if self.depth_limit.expands_from(graph, node, &self.parents) {
+                break (node, cost);
+            }
+            observer.on_prune(node);
+        };
+        observer.on_expand(node);
+        let cost_to_node = self.distances[&node];
+        let parent = self.parents.get(&node).copied();
+        for neighbour in pruned_neighbours(graph, node, parent) {
+            let Some(child) = Self::successor(graph, node, neighbour) else {
+                continue;
+            };
+            observer.on_generate(node, child);
+            let cost_to_child = cost_to_node + segment_cost(node, child);
+            if cost_to_child < self.distances.get(&child).copied().unwrap_or(i64::MAX) {
+                self.parents.insert(child, node);
+                self.distances.insert(child, cost_to_child);
+                self.frontier.push(HeapElement::new(
+                    child,
+                    cost_to_child + graph.heuristic(child),
+                ));
+            } else {
+                observer.on_duplicate(child);
+            }
+        }
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.frontier.len(),
+            f: Some(key),
+        })
+    }
+
+    fn solution(&self) -> Option<Cell> {
+        self.solution
+    }
+
+    fn solution_cost(&self, _graph: &GridGraph) -> Option<i64> {
+        self.solution.map(|goal| self.distances[&goal])
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl GraphSearcher<GridGraph> for JumpPointSearch {
+    fn search_tracked(&mut self, graph: &GridGraph, root: Cell) -> SearchResult<GridGraph> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &GridGraph, root: Cell) -> Option<Cell> {
+        Self::new().search_tracked(graph, root).goal()
+    }
+
+    fn is_visited(&self, node: Cell) -> bool {
+        self.distances.contains_key(&node)
+    }
+
+    /// The full cell-by-cell path, filling in the cells between jump points.
+    fn path(&self) -> Option<Vec<Cell>> {
+        let jump_points = self.jump_points()?;
+        let mut path = vec![jump_points[0]];
+        for pair in jump_points.windows(2) {
+            let (mut x, mut y) = signed(pair[0]);
+            let (tx, ty) = signed(pair[1]);
+            let (dx, dy) = ((tx - x).signum(), (ty - y).signum());
+            while (x, y) != (tx, ty) {
+                x += dx;
+                y += dy;
+                path.push(unsigned(x, y));
+            }
+        }
+        Some(path)
+    }
+
+    /// The direction of each step along `path`.
+    fn path_edges(&self) -> Option<Vec<Direction>> {
+        let path = self.path()?;
+        let directions = path.windows(2).map(|pair| {
+            let ((x, y), (tx, ty)) = (signed(pair[0]), signed(pair[1]));
+            Direction::from_offset(tx - x, ty - y).expect("adjacent cells")
+        });
+        Some(directions.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::graph::WeightedGraph;
+
+    /// A grid with roughly one cell in `density` blocked, from a fixed seed.
+    fn random_grid(size: usize, density: u64, seed: u64) -> GridGraph {
+        let mut state = seed;
+        let mut grid = GridGraph::new(size, size, Cell::new(0, 0), Cell::new(size - 1, size - 1));
+        for y in 0..size {
+            for x in 0..size {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let cell = Cell::new(x, y);
+                if state.is_multiple_of(density) && cell != grid.start() && cell != grid.goal() {
+                    grid.set_blocked(cell, true);
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn open_grid() {
+        let graph = GridGraph::new(32, 32, Cell::new(0, 0), Cell::new(31, 20));
+        let mut jps = JumpPointSearch::new();
+        let mut astar = AStar::new();
+        let jps_result = jps.search_tracked(&graph, graph.root());
+        let astar_result = astar.search_tracked(&graph, graph.root());
+        assert_eq!(jps_result.cost, astar_result.cost);
+        let path = jps.path().unwrap();
+        assert_eq!(path.first(), Some(&graph.root()));
+        assert_eq!(path.last(), Some(&graph.goal()));
+        assert_eq!(
+            graph.path_cost(&path),
+            graph.path_cost(&astar.path().unwrap())
+        );
+        assert!(jps_result.stats.expanded < astar_result.stats.expanded);
+    }
+
+    #[test]
+    fn matches_astar() {
+        for seed in 1..40 {
+            let graph = random_grid(24, 3, seed);
+            let mut jps = JumpPointSearch::new();
+            let mut astar = AStar::new();
+            let found = jps.search_tracked(&graph, graph.root()).goal();
+            assert_eq!(found, astar.search_tracked(&graph, graph.root()).goal());
+            if found.is_some() {
+                let path = jps.path().unwrap();
+                let directions = jps.path_edges().unwrap();
+                assert!(path
+                    .windows(2)
+                    .zip(directions)
+                    .all(|(w, d)| graph.successors(w[0]).contains(&(d, w[1]))));
+                assert_eq!(
+                    graph.path_cost(&path),
+                    graph.path_cost(&astar.path().unwrap())
+                );
+                assert_eq!(graph.path_cost(&path), jps.distances()[&graph.goal()]);
+            }
+        }
+    }
+
+    #[test]
+    fn four_connected() {
+        let mut graph = random_grid(16, 4, 7).with_connectivity(Connectivity::Four);
+        graph.set_goal(Cell::new(15, 0));
+        graph.set_blocked(Cell::new(15, 0), false);
+        let mut jps = JumpPointSearch::new();
+        let mut astar = AStar::new();
+        assert_eq!(
+            jps.search_tracked(&graph, graph.root()).goal(),
+            astar.search_tracked(&graph, graph.root()).goal()
+        );
+        if let Some(path) = jps.path() {
+            assert_eq!(
+                graph.path_cost(&path),
+                graph.path_cost(&astar.path().unwrap())
+            );
+        }
+    }
+}
@@ -3,36 +3,44 @@
 use graph::HeuristicGraph;
 
 pub mod astar;
+pub mod beam;
+pub mod bellmanford;
 pub mod bestfirst;
 pub mod bfs;
+pub mod budget;
+pub mod cost;
 pub mod dfs;
 pub mod dijkstra;
+#[cfg(test)]
 mod examplegraph;
 pub mod graph;
 pub mod graphsearcher;
+pub mod grid;
 mod heapelement;
+pub mod heuristiccheck;
+pub mod heuristics;
+pub mod idastar;
+pub mod jps;
+pub mod mcts;
+pub mod minimax;
+pub mod movingai;
+pub mod observer;
+pub mod patterndb;
 pub mod perft;
+pub mod rbfs;
+pub mod searchresult;
+pub mod smastar;
+pub mod tilepuzzle;
 
-pub fn gamut<G: graph::Graph>(game: &G) {
-    use crate::graphsearcher::GraphSearcher;
-    let mut breadthfirst = bfs::BFS::new();
-    let mut depthfirst = dfs::DFS::new();
-    let mut it_deep = dfs::IterDeepening::new();
+/// Prints what a search found, and how much work it took.
+fn report<G: graph::Graph>(name: &str, result: &searchresult::SearchResult<G>) {
     println!(
-        "bfs finds the solution {} \n bfs expands {} nodes. \n bfs finds the path \n{}\n the largest frontier maintained was {} nodes.\n", 
-        breadthfirst.search_tracked(game, game.root())
+        "{name} finds the solution {} \n {name} expands {} nodes. \n {name} finds the path \n{}\n the largest frontier maintained was {} nodes.\n",
+        result
+            .goal()
             .map_or_else(|| "[NO SOLUTION]".to_string(), |s| format!("{s}")),
-        breadthfirst.nodes_visited(),
-        breadthfirst.path().map_or_else(|| "no path".to_string(), |p| p.iter().map(|s| format!("{s}")).collect::<Vec<_>>().join("\n")),
-        breadthfirst.max_frontier()
-    );
-    println!(
-        "dfs finds the solution {} \n dfs expands {} nodes. \n dfs finds the path \n{}\n",
-        depthfirst
-            .search_tracked(game, game.root())
-            .map_or_else(|| "[NO SOLUTION]".to_string(), |s| format!("{s}")),
-        depthfirst.nodes_visited(),
-        depthfirst.path().map_or_else(
+        result.stats.expanded,
+        result.path.as_ref().map_or_else(
             || "no path".to_string(),
             |p| p
                 .iter()
@@ -40,24 +48,25 @@ pub fn gamut<G: graph::Graph>(game: &G) {
                 .collect::<Vec<_>>()
                 .join("\n")
         ),
+        result.stats.max_frontier,
     );
-    println!(
-        "iterative deepening dfs finds the solution {} \n iterative deepening dfs expands {} nodes. \n iterative deepening dfs finds the path \n{}\n", 
-        it_deep.search_tracked(game, game.root())
-            .map_or_else(|| "[NO SOLUTION]".to_string(), |s| format!("{s}")),
-        it_deep.nodes_visited(),
-        it_deep.path().map_or_else(|| "no path".to_string(), |p| p.iter().map(|s| format!("{s}")).collect::<Vec<_>>().join("\n")),
+}
+
+pub fn gamut<G: graph::Graph>(game: &G) {
+    use crate::graphsearcher::GraphSearcher;
+    let root = game.root();
+    report("bfs", &bfs::BFS::new().search_tracked(game, root));
+    report("dfs", &dfs::DFS::new().search_tracked(game, root));
+    report(
+        "iterative deepening dfs",
+        &dfs::IterDeepening::new().search_tracked(game, root),
     );
 }
 
 pub fn complex_gamut<G: graph::WeightedGraph + HeuristicGraph>(game: &G) {
     use crate::graphsearcher::GraphSearcher;
-    let mut bestfirst = bestfirst::BestFirstSearch::new();
-    println!(
-        "best first search finds the solution {} \n best first search expands {} nodes. \n best first search finds the path \n{}\n", 
-        bestfirst.search_tracked(game, game.root())
-            .map_or_else(|| "[NO SOLUTION]".to_string(), |s| format!("{s}")),
-        bestfirst.nodes_visited(),
-        bestfirst.path().map_or_else(|| "no path".to_string(), |p| p.iter().map(|s| format!("{s}")).collect::<Vec<_>>().join("\n")),
+    report(
+        "best first search",
+        &bestfirst::BestFirstSearch::new().search_tracked(game, game.root()),
     );
 }
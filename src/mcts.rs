@@ -0,0 +1,330 @@
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use crate::graph::{AdversarialGraph, Graph, Side};
+
+/// How much work a Monte Carlo tree search may do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulationBudget {
+    Iterations(usize),
+    Time(Duration),
+}
+
+/// Chooses the next move during a rollout.
+pub trait RolloutPolicy<G: Graph> {
+    fn choose(&mut self, graph: &G, node: G::Node, children: &[G::Node]) -> G::Node;
+}
+
+impl<G: Graph, F: FnMut(&G, G::Node, &[G::Node]) -> G::Node> RolloutPolicy<G> for F {
+    fn choose(&mut self, graph: &G, node: G::Node, children: &[G::Node]) -> G::Node {
+        self(graph, node, children)
+    }
+}
+
+/// A small splitmix64 generator, so rollouts and generated instances are
+/// reproducible from a seed.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) const fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) const fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Plays uniformly random moves.
+#[derive(Clone, Debug)]
+pub struct RandomRollout {
+    rng: SplitMix64,
+}
+
+impl RandomRollout {
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            rng: SplitMix64::new(seed),
+        }
+    }
+}
+
+impl Default for RandomRollout {
+    fn default() -> Self {
+        Self::new(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+impl<G: Graph> RolloutPolicy<G> for RandomRollout {
+    fn choose(&mut self, _graph: &G, _node: G::Node, children: &[G::Node]) -> G::Node {
+        children[self.rng.below(children.len())]
+    }
+}
+
+/// Search statistics for one child of the root. `value` is the mean reward,
+/// in `[0, 1]`, for the side that moves into this child.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChildStats<N> {
+    pub node: N,
+    pub visits: usize,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsResult<N> {
+    pub best_move: Option<N>,
+    pub iterations: usize,
+    pub children: Vec<ChildStats<N>>,
+}
+
+struct TreeNode<N> {
+    node: N,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    unexpanded: Vec<N>,
+    visits: usize,
+    /// total reward for the side to move at the parent.
+    reward: f64,
+}
+
+/// Monte Carlo tree search with UCT selection.
+///
+/// Rewards come from `AdversarialGraph::evaluate` at the end of each rollout:
+/// a positive score is a win for `Max`, a negative one a win for `Min`, and
+/// zero a draw.
+pub struct Mcts<G: AdversarialGraph, P: RolloutPolicy<G> = RandomRollout> {
+    budget: SimulationBudget,
+    exploration: f64,
+    max_rollout_depth: usize,
+    policy: P,
+    tree: Vec<TreeNode<G::Node>>,
+}
+
+impl<G: AdversarialGraph, P: RolloutPolicy<G>> Debug for Mcts<G, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Mcts")
+    }
+}
+
+impl<G: AdversarialGraph> Mcts<G> {
+    #[must_use]
+    pub fn new(budget: SimulationBudget) -> Self {
+        Self {
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            max_rollout_depth: usize::MAX,
+            policy: RandomRollout::default(),
+            tree: Vec::new(),
+        }
+    }
+}
+
+impl<G: AdversarialGraph, P: RolloutPolicy<G>> Mcts<G, P> {
+    /// Sets the UCT exploration constant, √2 by default.
+    #[must_use]
+    pub const fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Cuts rollouts off after `depth` moves, scoring the last node reached.
+    #[must_use]
+    pub const fn with_max_rollout_depth(mut self, depth: usize) -> Self {
+        self.max_rollout_depth = depth;
+        self
+    }
+
+    #[must_use]
+    pub fn with_rollout_policy<Q: RolloutPolicy<G>>(self, policy: Q) -> Mcts<G, Q> {
+        Mcts {
+            budget: self.budget,
+            exploration: self.exploration,
+            max_rollout_depth: self.max_rollout_depth,
+            policy,
+            tree: Vec::new(),
+        }
+    }
+
+    /// The number of tree nodes built by the most recent search.
+    #[must_use]
+    pub const fn nodes_visited(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn search(&mut self, graph: &G, root: G::Node) -> MctsResult<G::Node> {
+        self.tree.clear();
+        self.tree.push(TreeNode {
+            node: root,
+            parent: None,
+            children: Vec::new(),
+            unexpanded: graph.children(root),
+            visits: 0,
+            reward: 0.0,
+        });
+
+        let start = Instant::now();
+        let mut iterations = 0;
+        while match self.budget {
+            SimulationBudget::Iterations(n) => iterations < n,
+            SimulationBudget::Time(limit) => start.elapsed() < limit,
+        } {
+            let leaf = self.expand(graph, self.select());
+            let reward = self.rollout(graph, self.tree[leaf].node);
+            self.backpropagate(graph, leaf, reward);
+            iterations += 1;
+        }
+
+        let children = self.tree[0]
+            .children
+            .iter()
+            .map(|&i| {
+                let child = &self.tree[i];
+                ChildStats {
+                    node: child.node,
+                    visits: child.visits,
+                    value: mean(child.reward, child.visits),
+                }
+            })
+            .collect::<Vec<_>>();
+        MctsResult {
+            best_move: children.iter().max_by_key(|c| c.visits).map(|c| c.node),
+            iterations,
+            children,
+        }
+    }
+
+    /// Descends from the root through fully expanded nodes by UCT.
+    fn select(&self) -> usize {
+        let mut index = 0;
+        loop {
+            let current = &self.tree[index];
+            if !current.unexpanded.is_empty() || current.children.is_empty() {
+                return index;
+            }
+            #[allow(clippy::cast_precision_loss)]
+            let log_visits = (current.visits as f64).ln();
+            index = *current
+                .children
+                .iter()
+                .max_by(|&&a, &&b| self.uct(a, log_visits).total_cmp(&self.uct(b, log_visits)))
+                .expect("a fully expanded node with children");
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn uct(&self, index: usize, parent_log_visits: f64) -> f64 {
+        let child = &self.tree[index];
+        self.exploration.mul_add(
+            (parent_log_visits / child.visits as f64).sqrt(),
+            mean(child.reward, child.visits),
+        )
+    }
+
+    /// Adds one unexpanded child of `index` to the tree, if it has any.
+    fn expand(&mut self, graph: &G, index: usize) -> usize {
+        let Some(node) = self.tree[index].unexpanded.pop() else {
+            return index;
+        };
+        let child = self.tree.len();
+        self.tree.push(TreeNode {
+            node,
+            parent: Some(index),
+            children: Vec::new(),
+            unexpanded: graph.children(node),
+            visits: 0,
+            reward: 0.0,
+        });
+        self.tree[index].children.push(child);
+        child
+    }
+
+    /// Plays out from `node`, returning the reward for `Max`.
+    fn rollout(&mut self, graph: &G, mut node: G::Node) -> f64 {
+        for _ in 0..self.max_rollout_depth {
+            let children = graph.children(node);
+            if children.is_empty() {
+                break;
+            }
+            node = self.policy.choose(graph, node, &children);
+        }
+        match graph.evaluate(node).signum() {
+            1 => 1.0,
+            -1 => 0.0,
+            _ => 0.5,
+        }
+    }
+
+    fn backpropagate(&mut self, graph: &G, leaf: usize, reward: f64) {
+        let mut index = Some(leaf);
+        while let Some(i) = index {
+            let parent = self.tree[i].parent;
+            let mover = parent.map_or(Side::Max, |p| graph.side_to_move(self.tree[p].node));
+            let current = &mut self.tree[i];
+            current.visits += 1;
+            current.reward += match mover {
+                Side::Max => reward,
+                Side::Min => 1.0 - reward,
+            };
+            index = parent;
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn mean(total: f64, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examplegraph::Nim;
+    use crate::graph::Graph;
+
+    #[test]
+    fn finds_winning_move() {
+        let mut searcher = Mcts::new(SimulationBudget::Iterations(3000));
+        let result = searcher.search(&Nim, Nim.root());
+        assert_eq!(result.iterations, 3000);
+        assert_eq!(result.children.len(), 3);
+        // take one stone, leaving a multiple of four.
+        assert_eq!(result.best_move, Some(4 * 2 + 1));
+        assert_eq!(
+            result.children.iter().map(|c| c.visits).sum::<usize>(),
+            3000
+        );
+    }
+
+    #[test]
+    fn custom_policy_and_time_budget() {
+        // always take a single stone.
+        let policy = |_: &Nim, _: usize, children: &[usize]| children[0];
+        let mut searcher = Mcts::new(SimulationBudget::Time(Duration::from_millis(10)))
+            .with_rollout_policy(policy)
+            .with_max_rollout_depth(4);
+        let result = searcher.search(&Nim, Nim.root());
+        assert!(result.iterations > 0);
+        assert!(result.best_move.is_some());
+        assert!(result
+            .children
+            .iter()
+            .all(|c| (0.0..=1.0).contains(&c.value)));
+    }
+}
@@ -0,0 +1,341 @@
+use std::fmt::Debug;
+use std::time::Instant;
+
+use crate::graph::AdversarialGraph;
+
+/// The outcome of a game-tree search. `score` is from `Max`'s perspective, and
+/// `principal_variation` is the expected line of play, starting with `best_move`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameSearchResult<N> {
+    pub best_move: Option<N>,
+    pub score: i64,
+    pub principal_variation: Vec<N>,
+}
+
+impl<N: Copy> GameSearchResult<N> {
+    fn new(score: i64, principal_variation: Vec<N>) -> Self {
+        Self {
+            best_move: principal_variation.first().copied(),
+            score,
+            principal_variation,
+        }
+    }
+}
+
+/// Full-width minimax to a fixed depth.
+pub struct Minimax<G: AdversarialGraph> {
+    counter: usize,
+    _graph: std::marker::PhantomData<G>,
+}
+
+impl<G: AdversarialGraph> Debug for Minimax<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Minimax")
+    }
+}
+
+impl<G: AdversarialGraph> Minimax<G> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            counter: 0,
+            _graph: std::marker::PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub const fn nodes_visited(&self) -> usize {
+        self.counter
+    }
+
+    pub fn search(&mut self, graph: &G, root: G::Node, depth: usize) -> GameSearchResult<G::Node> {
+        self.counter = 0;
+        let mut pv = Vec::new();
+        let score = self.minimax(graph, root, depth, &mut pv);
+        GameSearchResult::new(score, pv)
+    }
+
+    fn minimax(&mut self, graph: &G, node: G::Node, depth: usize, pv: &mut Vec<G::Node>) -> i64 {
+        self.counter += 1;
+        pv.clear();
+        let children = graph.children(node);
+        if depth == 0 || children.is_empty() {
+            return graph.evaluate(node);
+        }
+        let side = graph.side_to_move(node);
+        let mut best = None;
+        let mut child_pv = Vec::new();
+        for child in children {
+            let score = self.minimax(graph, child, depth - 1, &mut child_pv);
+            if best.is_none_or(|b| side.sign() * score > side.sign() * b) {
+                best = Some(score);
+                pv.clear();
+                pv.push(child);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        best.unwrap_or_else(|| graph.evaluate(node))
+    }
+}
+
+impl<G: AdversarialGraph> Default for Minimax<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Negamax with alpha-beta pruning to a fixed depth.
+///
+/// Scores are negated only when the side to move changes, so games with
+/// passes or extra moves are handled correctly.
+pub struct AlphaBeta<G: AdversarialGraph> {
+    counter: usize,
+    deadline: Option<Instant>,
+    _graph: std::marker::PhantomData<G>,
+}
+
+impl<G: AdversarialGraph> Debug for AlphaBeta<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AlphaBeta")
+    }
+}
+
+impl<G: AdversarialGraph> AlphaBeta<G> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            counter: 0,
+            deadline: None,
+            _graph: std::marker::PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub const fn nodes_visited(&self) -> usize {
+        self.counter
+    }
+
+    // without a deadline the search is never aborted, so this can't panic.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn search(&mut self, graph: &G, root: G::Node, depth: usize) -> GameSearchResult<G::Node> {
+        self.counter = 0;
+        self.deadline = None;
+        self.search_with_hint(graph, root, depth, &[])
+            .expect("search without a deadline cannot be aborted")
+    }
+
+    /// Searches to `depth`, trying the moves of `hint` first. Returns `None` if
+    /// the deadline passed before the search completed.
+    fn search_with_hint(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        depth: usize,
+        hint: &[G::Node],
+    ) -> Option<GameSearchResult<G::Node>> {
+        let mut pv = Vec::new();
+        let score = self.negamax(graph, root, depth, -i64::MAX, i64::MAX, &mut pv, hint)?;
+        Some(GameSearchResult::new(
+            graph.side_to_move(root).sign() * score,
+            pv,
+        ))
+    }
+
+    /// Returns the score of `node` from the perspective of its side to move.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        graph: &G,
+        node: G::Node,
+        depth: usize,
+        mut alpha: i64,
+        beta: i64,
+        pv: &mut Vec<G::Node>,
+        hint: &[G::Node],
+    ) -> Option<i64> {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return None;
+        }
+        self.counter += 1;
+        pv.clear();
+        let side = graph.side_to_move(node);
+        let mut children = graph.children(node);
+        if depth == 0 || children.is_empty() {
+            return Some(side.sign() * graph.evaluate(node));
+        }
+        // search the previous principal variation first, it's the likeliest
+        // move to produce a cutoff.
+        let hinted = hint
+            .first()
+            .and_then(|h| children.iter().position(|c| c == h));
+        if let Some(index) = hinted {
+            children[..=index].rotate_right(1);
+        }
+        let mut best = -i64::MAX;
+        let mut child_pv = Vec::new();
+        for (i, child) in children.into_iter().enumerate() {
+            let child_hint = if i == 0 && hinted.is_some() {
+                &hint[1..]
+            } else {
+                &[]
+            };
+            let score = if graph.side_to_move(child) == side {
+                self.negamax(
+                    graph,
+                    child,
+                    depth - 1,
+                    alpha,
+                    beta,
+                    &mut child_pv,
+                    child_hint,
+                )?
+            } else {
+                -self.negamax(
+                    graph,
+                    child,
+                    depth - 1,
+                    -beta,
+                    -alpha,
+                    &mut child_pv,
+                    child_hint,
+                )?
+            };
+            if score > best || pv.is_empty() {
+                best = score;
+                pv.clear();
+                pv.push(child);
+                pv.extend_from_slice(&child_pv);
+            }
+            alpha = std::cmp::max(alpha, score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
+
+impl<G: AdversarialGraph> Default for AlphaBeta<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Alpha-beta search repeated at increasing depths, each iteration trying
+/// the previous principal variation first.
+pub struct IterDeepeningAlphaBeta<G: AdversarialGraph> {
+    inner: AlphaBeta<G>,
+    completed_depth: usize,
+}
+
+impl<G: AdversarialGraph> Debug for IterDeepeningAlphaBeta<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IterDeepeningAlphaBeta")
+    }
+}
+
+impl<G: AdversarialGraph> IterDeepeningAlphaBeta<G> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: AlphaBeta::new(),
+            completed_depth: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn nodes_visited(&self) -> usize {
+        self.inner.counter
+    }
+
+    /// The deepest iteration that completed in the most recent search.
+    #[must_use]
+    pub const fn completed_depth(&self) -> usize {
+        self.completed_depth
+    }
+
+    /// Searches to depths `1..=max_depth`, stopping early if `deadline` passes.
+    /// Returns the result of the deepest completed iteration, or `None` if not
+    /// even the first one completed.
+    pub fn search(
+        &mut self,
+        graph: &G,
+        root: G::Node,
+        max_depth: usize,
+        deadline: Option<Instant>,
+    ) -> Option<GameSearchResult<G::Node>> {
+        self.inner.counter = 0;
+        self.inner.deadline = deadline;
+        self.completed_depth = 0;
+        let mut best = None;
+        for depth in 1..=max_depth {
+            let hint = best
+                .as_ref()
+                .map_or_else(Vec::new, |r: &GameSearchResult<G::Node>| {
+                    r.principal_variation.clone()
+                });
+            let Some(result) = self.inner.search_with_hint(graph, root, depth, &hint) else {
+                break;
+            };
+            self.completed_depth = depth;
+            best = Some(result);
+        }
+        self.inner.deadline = None;
+        best
+    }
+}
+
+impl<G: AdversarialGraph> Default for IterDeepeningAlphaBeta<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examplegraph::Nim;
+    use crate::graph::Graph;
+
+    #[test]
+    fn minimax() {
+        let mut searcher = Minimax::new();
+        let result = searcher.search(&Nim, Nim.root(), 5);
+        assert_eq!(result.score, 1);
+        // take one stone, leaving a multiple of four.
+        assert_eq!(result.best_move, Some(4 * 2 + 1));
+        assert_eq!(
+            result.principal_variation.first(),
+            result.best_move.as_ref()
+        );
+        assert!(Nim.is_goal(*result.principal_variation.last().unwrap()));
+    }
+
+    #[test]
+    fn alphabeta_agrees_with_minimax() {
+        for stones in 1..=9 {
+            for depth in 1..=6 {
+                let root = stones * 2;
+                let mut minimax = Minimax::new();
+                let mut alphabeta = AlphaBeta::new();
+                let expected = minimax.search(&Nim, root, depth);
+                let result = alphabeta.search(&Nim, root, depth);
+                assert_eq!(result.score, expected.score);
+                assert!(alphabeta.nodes_visited() <= minimax.nodes_visited());
+            }
+        }
+    }
+
+    #[test]
+    fn iterative_deepening() {
+        let mut searcher = IterDeepeningAlphaBeta::new();
+        let result = searcher.search(&Nim, 8 * 2 + 1, 8, None).unwrap();
+        assert_eq!(searcher.completed_depth(), 8);
+        // Min is to move on a multiple of four, and loses.
+        assert_eq!(result.score, 1);
+        assert_eq!(result.best_move.map(|n| n % 2), Some(0));
+        assert!(searcher
+            .search(&Nim, Nim.root(), 8, Some(Instant::now()))
+            .is_none());
+    }
+}
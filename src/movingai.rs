@@ -0,0 +1,355 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::graph::WeightedGraph;
+use crate::graphsearcher::GraphSearcher;
+use crate::grid::{Cell, GridGraph, DIAGONAL_COST, STRAIGHT_COST};
+use crate::searchresult::SearchStats;
+
+#[derive(Debug)]
+pub enum MovingAiError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl Display for MovingAiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for MovingAiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MovingAiError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> MovingAiError {
+    MovingAiError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(
+    line: usize,
+    name: &str,
+    value: &str,
+) -> Result<T, MovingAiError> {
+    value
+        .parse()
+        .map_err(|_| parse_error(line, format!("invalid {name} {value:?}")))
+}
+
+/// Parses a Moving AI Lab `.map` file describing an octile grid.
+///
+/// Ground (`.`, `G`) and swamp (`S`) cells are passable, everything else is
+/// blocked. The start and goal are both set to the top-left corner, to be
+/// replaced per query.
+///
+/// # Errors
+///
+/// Returns an error if the header or grid is malformed.
+pub fn parse_map(text: &str) -> Result<GridGraph, MovingAiError> {
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l.trim_end()));
+    let mut header = |expected: &str| {
+        let (number, line) = lines
+            .next()
+            .ok_or_else(|| parse_error(0, format!("missing {expected} line")))?;
+        let mut words = line.split_whitespace();
+        if words.next() != Some(expected) {
+            return Err(parse_error(number, format!("expected {expected:?}")));
+        }
+        Ok((number, words.next().unwrap_or_default().to_string()))
+    };
+    let (number, kind) = header("type")?;
+    if kind != "octile" {
+        return Err(parse_error(
+            number,
+            format!("unsupported map type {kind:?}"),
+        ));
+    }
+    let (number, height) = header("height")?;
+    let height: usize = parse_field(number, "height", &height)?;
+    let (number, width) = header("width")?;
+    let width: usize = parse_field(number, "width", &width)?;
+    header("map")?;
+
+    let mut grid = GridGraph::new(width, height, Cell::new(0, 0), Cell::new(0, 0));
+    for y in 0..height {
+        let (number, row) = lines
+            .next()
+            .ok_or_else(|| parse_error(0, format!("missing map row {y}")))?;
+        if row.len() != width {
+            return Err(parse_error(
+                number,
+                format!("expected {width} cells, found {}", row.len()),
+            ));
+        }
+        for (x, c) in row.bytes().enumerate() {
+            grid.set_blocked(Cell::new(x, y), !matches!(c, b'.' | b'G' | b'S'));
+        }
+    }
+    Ok(grid)
+}
+
+/// Reads and parses a `.map` file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or is malformed.
+pub fn load_map(path: impl AsRef<Path>) -> Result<GridGraph, MovingAiError> {
+    parse_map(&std::fs::read_to_string(path)?)
+}
+
+/// A single query from a `.scen` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub bucket: usize,
+    pub map: String,
+    pub width: usize,
+    pub height: usize,
+    pub start: Cell,
+    pub goal: Cell,
+    pub optimal_length: f64,
+    /// The line of the `.scen` file the query was read from.
+    pub line: usize,
+}
+
+/// Parses a Moving AI Lab `.scen` file listing queries on a map.
+///
+/// # Errors
+///
+/// Returns an error if the version line or any query is malformed, or a
+/// query's start or goal lies outside its map.
+pub fn parse_scenarios(text: &str) -> Result<Vec<Scenario>, MovingAiError> {
+    let mut scenarios = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        if line.trim().is_empty() || (number == 1 && line.starts_with("version")) {
+            continue;
+        }
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let [bucket, map, width, height, start_x, start_y, goal_x, goal_y, optimal_length] =
+            fields[..]
+        else {
+            return Err(parse_error(
+                number,
+                format!("expected 9 fields, found {}", fields.len()),
+            ));
+        };
+        let scenario = Scenario {
+            bucket: parse_field(number, "bucket", bucket)?,
+            map: map.to_string(),
+            width: parse_field(number, "width", width)?,
+            height: parse_field(number, "height", height)?,
+            start: Cell::new(
+                parse_field(number, "start x", start_x)?,
+                parse_field(number, "start y", start_y)?,
+            ),
+            goal: Cell::new(
+                parse_field(number, "goal x", goal_x)?,
+                parse_field(number, "goal y", goal_y)?,
+            ),
+            optimal_length: parse_field(number, "optimal length", optimal_length)?,
+            line: number,
+        };
+        for (name, cell) in [("start", scenario.start), ("goal", scenario.goal)] {
+            if cell.x >= scenario.width || cell.y >= scenario.height {
+                return Err(parse_error(
+                    number,
+                    format!(
+                        "{name} {cell} is outside the {}x{} map",
+                        scenario.width, scenario.height
+                    ),
+                ));
+            }
+        }
+        scenarios.push(scenario);
+    }
+    Ok(scenarios)
+}
+
+/// Reads and parses a `.scen` file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or is malformed.
+pub fn load_scenarios(path: impl AsRef<Path>) -> Result<Vec<Scenario>, MovingAiError> {
+    parse_scenarios(&std::fs::read_to_string(path)?)
+}
+
+/// The outcome of running a searcher on one scenario. `length` is in Moving
+/// AI units, and `None` if no path was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioResult {
+    pub index: usize,
+    pub expected: f64,
+    pub length: Option<f64>,
+    pub stats: SearchStats,
+    pub optimal: bool,
+}
+
+/// Whether a path cost, in `GridGraph` units, is optimal for a reference
+/// length. Diagonal steps cost slightly less than √2 in the grid, so the
+/// cost may fall short of the reference by up to that rounding.
+#[allow(clippy::cast_precision_loss)]
+fn is_optimal(cost: i64, optimal_length: f64) -> bool {
+    let reference = optimal_length * STRAIGHT_COST as f64;
+    let rounding = DIAGONAL_COST as f64 / (std::f64::consts::SQRT_2 * STRAIGHT_COST as f64);
+    let cost = cost as f64;
+    cost <= reference + 1.0 && cost >= reference.mul_add(rounding, -1.0)
+}
+
+/// Runs `searcher` on every scenario in turn, checking each path's cost
+/// against the reference optimum. Scenarios for a map of a different size
+/// are reported as not optimal.
+///
+/// Moving AI measures paths with straight steps of 1 and diagonal steps of
+/// √2, so lengths are reported as `GridGraph` costs divided by
+/// `STRAIGHT_COST`.
+///
+/// # Errors
+///
+/// Returns an error, before running any search, if a scenario for this map
+/// starts or ends on a blocked cell.
+#[allow(clippy::cast_precision_loss)]
+pub fn run_scenarios<S: GraphSearcher<GridGraph>>(
+    searcher: &mut S,
+    grid: &mut GridGraph,
+    scenarios: &[Scenario],
+) -> Result<Vec<ScenarioResult>, MovingAiError> {
+    for scenario in scenarios {
+        if (scenario.width, scenario.height) != (grid.width(), grid.height()) {
+            continue;
+        }
+        for (name, cell) in [("start", scenario.start), ("goal", scenario.goal)] {
+            if grid.is_blocked(cell) {
+                return Err(parse_error(
+                    scenario.line,
+                    format!("{name} {cell} is blocked"),
+                ));
+            }
+        }
+    }
+    Ok(scenarios
+        .iter()
+        .enumerate()
+        .map(|(index, scenario)| {
+            if (scenario.width, scenario.height) != (grid.width(), grid.height()) {
+                return ScenarioResult {
+                    index,
+                    expected: scenario.optimal_length,
+                    length: None,
+                    stats: SearchStats::default(),
+                    optimal: false,
+                };
+            }
+            grid.set_start(scenario.start);
+            grid.set_goal(scenario.goal);
+            let result = searcher.search_tracked(grid, scenario.start);
+            let cost = result.path.map(|path| grid.path_cost(&path));
+            ScenarioResult {
+                index,
+                expected: scenario.optimal_length,
+                length: cost.map(|c| c as f64 / STRAIGHT_COST as f64),
+                stats: result.stats,
+                optimal: cost.is_some_and(|c| is_optimal(c, scenario.optimal_length)),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::dijkstra::Dijkstra;
+    use crate::jps::JumpPointSearch;
+
+    const MAP: &str = "type octile
+height 5
+width 5
+map
+.....
+.@@@.
+.....
+TT...
+.....
+";
+
+    const SCENARIOS: &str = "version 1
+0\ttest.map\t5\t5\t0\t0\t4\t0\t4.00000000
+0\ttest.map\t5\t5\t0\t0\t4\t2\t6.00000000
+1\ttest.map\t5\t5\t0\t2\t4\t4\t4.82842712
+1\ttest.map\t5\t5\t0\t4\t0\t0\t8.00000000
+";
+
+    #[test]
+    fn parse() {
+        let grid = parse_map(MAP).unwrap();
+        assert_eq!((grid.width(), grid.height()), (5, 5));
+        assert!(grid.is_blocked(Cell::new(2, 1)));
+        assert!(grid.is_blocked(Cell::new(0, 3)));
+        assert!(!grid.is_blocked(Cell::new(2, 3)));
+        let scenarios = parse_scenarios(SCENARIOS).unwrap();
+        assert_eq!(scenarios.len(), 4);
+        assert_eq!(scenarios[2].start, Cell::new(0, 2));
+        assert_eq!(scenarios[2].goal, Cell::new(4, 4));
+        assert_eq!(scenarios[3].bucket, 1);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!(
+            parse_map("type octile\nheight 2\nwidth 2\nmap\n..\n."),
+            Err(MovingAiError::Parse { line: 6, .. })
+        ));
+        assert!(parse_map("type tile\nheight 1\nwidth 1\nmap\n.").is_err());
+        assert!(matches!(
+            parse_scenarios("version 1\n0 a.map 1 1 0 0 x 0 1.0"),
+            Err(MovingAiError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_scenarios("version 1\n0 a.map 5 5 0 0 4 4 1.0\n0 a.map 5 5 0 0 5 0 1.0"),
+            Err(MovingAiError::Parse { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn harness() {
+        let mut grid = parse_map(MAP).unwrap();
+        let scenarios = parse_scenarios(SCENARIOS).unwrap();
+        let results = run_scenarios(&mut AStar::new(), &mut grid, &scenarios).unwrap();
+        assert!(results.iter().all(|r| r.optimal), "{results:?}");
+        let results = run_scenarios(&mut Dijkstra::new(), &mut grid, &scenarios).unwrap();
+        assert!(results.iter().all(|r| r.optimal), "{results:?}");
+        let results = run_scenarios(&mut JumpPointSearch::new(), &mut grid, &scenarios).unwrap();
+        assert!(results.iter().all(|r| r.optimal), "{results:?}");
+
+        let mut wrong = scenarios[0].clone();
+        wrong.optimal_length = 3.0;
+        let results = run_scenarios(&mut AStar::new(), &mut grid, &[wrong]).unwrap();
+        assert!(!results[0].optimal);
+
+        let mut blocked = scenarios[0].clone();
+        blocked.goal = Cell::new(2, 1);
+        assert!(matches!(
+            run_scenarios(&mut AStar::new(), &mut grid, &[blocked]),
+            Err(MovingAiError::Parse { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::graph::Graph;
+
+/// Callbacks made by a searcher as it runs, for logging, profiling or
+/// visualising a search without changing the algorithm.
+///
+/// Every callback does nothing by default. `()` is the observer searchers
+/// start with, and a `&mut` observer can be passed so the caller keeps it.
+pub trait SearchObserver<G: Graph> {
+    /// A node is about to have its children generated.
+    fn on_expand(&mut self, _node: G::Node) {}
+
+    /// `child` was generated as a successor of `parent`. One of the other
+    /// callbacks may follow if the child is then discarded or reopened.
+    fn on_generate(&mut self, _parent: G::Node, _child: G::Node) {}
+
+    /// A generated node was discarded because it had been reached before at
+    /// no greater cost, or lies on the current path.
+    fn on_duplicate(&mut self, _node: G::Node) {}
+
+    /// A node that had already been expanded was reached more cheaply, and
+    /// will be expanded again.
+    fn on_reopen(&mut self, _node: G::Node) {}
+
+    /// The search accepted `node` as its solution.
+    fn on_goal(&mut self, _node: G::Node) {}
+
+    /// A node was cut off by a bound rather than as a duplicate: a depth or
+    /// f-limit, a beam width or a memory limit.
+    fn on_prune(&mut self, _node: G::Node) {}
+}
+
+impl<G: Graph> SearchObserver<G> for () {}
+
+impl<G: Graph, O: SearchObserver<G>> SearchObserver<G> for &mut O {
+    fn on_expand(&mut self, node: G::Node) {
+        (**self).on_expand(node);
+    }
+
+    fn on_generate(&mut self, parent: G::Node, child: G::Node) {
+        (**self).on_generate(parent, child);
+    }
+
+    fn on_duplicate(&mut self, node: G::Node) {
+        (**self).on_duplicate(node);
+    }
+
+    fn on_reopen(&mut self, node: G::Node) {
+        (**self).on_reopen(node);
+    }
+
+    fn on_goal(&mut self, node: G::Node) {
+        (**self).on_goal(node);
+    }
+
+    fn on_prune(&mut self, node: G::Node) {
+        (**self).on_prune(node);
+    }
+}
+
+/// Forwards every callback to both observers, the first one first.
+impl<G: Graph, A: SearchObserver<G>, B: SearchObserver<G>> SearchObserver<G> for (A, B) {
+    fn on_expand(&mut self, node: G::Node) {
+        self.0.on_expand(node);
+        self.1.on_expand(node);
+    }
+
+    fn on_generate(&mut self, parent: G::Node, child: G::Node) {
+        self.0.on_generate(parent, child);
+        self.1.on_generate(parent, child);
+    }
+
+    fn on_duplicate(&mut self, node: G::Node) {
+        self.0.on_duplicate(node);
+        self.1.on_duplicate(node);
+    }
+
+    fn on_reopen(&mut self, node: G::Node) {
+        self.0.on_reopen(node);
+        self.1.on_reopen(node);
+    }
+
+    fn on_goal(&mut self, node: G::Node) {
+        self.0.on_goal(node);
+        self.1.on_goal(node);
+    }
+
+    fn on_prune(&mut self, node: G::Node) {
+        self.0.on_prune(node);
+        self.1.on_prune(node);
+    }
+}
+
+/// Counts the callbacks of each kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountingObserver {
+    pub expanded: usize,
+    pub generated: usize,
+    pub duplicates: usize,
+    pub reopened: usize,
+    pub goals: usize,
+    pub pruned: usize,
+}
+
+impl CountingObserver {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<G: Graph> SearchObserver<G> for CountingObserver {
+    fn on_expand(&mut self, _node: G::Node) {
+        self.expanded += 1;
+    }
+
+    fn on_generate(&mut self, _parent: G::Node, _child: G::Node) {
+        self.generated += 1;
+    }
+
+    fn on_duplicate(&mut self, _node: G::Node) {
+        self.duplicates += 1;
+    }
+
+    fn on_reopen(&mut self, _node: G::Node) {
+        self.reopened += 1;
+    }
+
+    fn on_goal(&mut self, _node: G::Node) {
+        self.goals += 1;
+    }
+
+    fn on_prune(&mut self, _node: G::Node) {
+        self.pruned += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::beam::BeamSearch;
+    use crate::bfs::BFS;
+    use crate::dfs::IterDeepening;
+    use crate::examplegraph::{get_example_graph, ExampleGraph, ExampleNode};
+    use crate::graphsearcher::StepSearcher;
+    use crate::idastar::IdaStar;
+    use crate::smastar::SmaStar;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Expand(ExampleNode),
+        Generate(ExampleNode, ExampleNode),
+        Duplicate(ExampleNode),
+        Goal(ExampleNode),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<Event>);
+
+    impl SearchObserver<ExampleGraph> for Recorder {
+        fn on_expand(&mut self, node: ExampleNode) {
+            self.0.push(Event::Expand(node));
+        }
+
+        fn on_generate(&mut self, parent: ExampleNode, child: ExampleNode) {
+            self.0.push(Event::Generate(parent, child));
+        }
+
+        fn on_duplicate(&mut self, node: ExampleNode) {
+            self.0.push(Event::Duplicate(node));
+        }
+
+        fn on_goal(&mut self, node: ExampleNode) {
+            self.0.push(Event::Goal(node));
+        }
+    }
+
+    fn count<S: StepSearcher<ExampleGraph>>(mut searcher: S) -> CountingObserver {
+        let graph = get_example_graph();
+        let mut counts = CountingObserver::new();
+        searcher.search_observed(&graph, graph.root(), &mut counts);
+        counts
+    }
+
+    #[test]
+    fn events() {
+        let graph = get_example_graph();
+        let n = ExampleNode::new;
+        let mut recorder = Recorder::default();
+        let found = BFS::new()
+            .search_observed(&graph, graph.root(), &mut recorder)
+            .goal();
+        assert_eq!(found, Some(n(7)));
+        assert_eq!(
+            recorder.0[..4],
+            [
+                Event::Expand(n(8)),
+                Event::Generate(n(8), n(3)),
+                Event::Generate(n(8), n(10)),
+                Event::Expand(n(3))
+            ]
+        );
+        assert_eq!(recorder.0.last(), Some(&Event::Goal(n(7))));
+        assert!(!recorder.0.iter().any(|e| matches!(e, Event::Duplicate(_))));
+    }
+
+    #[test]
+    fn counts() {
+        let graph = get_example_graph();
+        let counts = count(BFS::new());
+        assert_eq!(
+            counts.expanded,
+            BFS::new().steps(&graph, graph.root()).count()
+        );
+        assert_eq!((counts.generated, counts.goals), (8, 1));
+
+        let counts = count(AStar::new());
+        assert_eq!((counts.goals, counts.reopened), (1, 0));
+        assert!(counts.expanded <= counts.generated);
+
+        // a beam of one drops 10, then 6, and runs dry.
+        let counts = count(BeamSearch::new(1));
+        assert_eq!((counts.goals, counts.pruned), (0, 2));
+
+        // depth and f-limits cut nodes off in all but the last iteration.
+        assert!(count(IterDeepening::new()).pruned > 0);
+        assert!(count(IdaStar::new()).pruned > 0);
+        assert!(count(SmaStar::new(4)).pruned > 0);
+    }
+}
@@ -0,0 +1,612 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::cost::Cost;
+use crate::graph::{Graph, HeuristicGraph, WeightedGraph};
+
+/// Stands in for the objects an abstraction doesn't track.
+pub const DONT_CARE: u8 = u8::MAX;
+
+const UNREACHED: u8 = u8::MAX;
+const MAGIC: &[u8; 4] = b"PDB1";
+
+/// A domain whose states are arrangements of numbered objects over a fixed
+/// set of positions, like the tiles of a sliding-tile puzzle. Every move must
+/// be reversible and cost 1.
+pub trait PermutationGraph: Graph {
+    /// The object in each position of `node`.
+    fn arrangement(&self, node: Self::Node) -> Vec<u8>;
+
+    fn goal_arrangement(&self) -> Vec<u8>;
+
+    /// Objects that every abstraction keeps track of, because moves can't be
+    /// generated without them, such as the blank of a sliding-tile puzzle.
+    fn pinned(&self) -> Vec<u8>;
+
+    /// The arrangements one move away from `arrangement`, each with the
+    /// object that moved. Objects that aren't tracked appear as `DONT_CARE`.
+    fn arrangement_moves(&self, arrangement: &[u8]) -> Vec<(Vec<u8>, u8)>;
+}
+
+/// The number of ways to place `objects` distinct objects in `cells`
+/// positions, or `None` if that doesn't fit in a `usize`.
+fn table_size(objects: usize, cells: usize) -> Option<usize> {
+    (cells - objects + 1..=cells).try_fold(1, usize::checked_mul)
+}
+
+/// Ranks the positions of distinct objects as a mixed-radix number, so a
+/// prefix of the objects ranks to a prefix of the digits.
+fn rank(positions: &[usize], cells: usize) -> usize {
+    positions.iter().enumerate().fold(0, |rank, (i, &p)| {
+        let earlier = positions[..i].iter().filter(|&&q| q < p).count();
+        rank * (cells - i) + p - earlier
+    })
+}
+
+fn unrank(mut rank: usize, objects: usize, cells: usize) -> Vec<usize> {
+    let mut digits = vec![0; objects];
+    for i in (0..objects).rev() {
+        digits[i] = rank % (cells - i);
+        rank /= cells - i;
+    }
+    let mut free = (0..cells).collect::<Vec<_>>();
+    digits.into_iter().map(|d| free.remove(d)).collect()
+}
+
+fn locate(objects: &[u8], arrangement: &[u8]) -> Vec<usize> {
+    objects
+        .iter()
+        .map(|o| {
+            arrangement
+                .iter()
+                .position(|x| x == o)
+                .expect("object is in the arrangement")
+        })
+        .collect()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// `value` as a byte of the file format, or an `InvalidInput` error naming
+/// `what` if it doesn't fit.
+fn to_byte(value: usize, what: &str) -> io::Result<u8> {
+    u8::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{what} doesn't fit in a byte"),
+        )
+    })
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buffer = [0; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+/// Exact distances to the goal in an abstraction of a `PermutationGraph`
+/// that only tells apart the objects of a pattern, one byte per placement of
+/// the pattern.
+///
+/// An additive database only counts moves of pattern objects, so the lookups
+/// of databases over disjoint patterns can be summed.
+#[derive(Clone)]
+pub struct PatternDatabase {
+    pattern: Vec<u8>,
+    cells: usize,
+    additive: bool,
+    distances: Vec<u8>,
+}
+
+impl Debug for PatternDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PatternDatabase({:?}, {} entries)",
+            self.pattern,
+            self.len()
+        )
+    }
+}
+
+impl PatternDatabase {
+    /// Runs a breadth-first search backwards from the goal over the
+    /// placements of the pattern and pinned objects, then keeps the smallest
+    /// distance for each placement of the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pattern object isn't in the goal, the tables would be too
+    /// large to address, or an abstract distance doesn't fit in a byte.
+    #[must_use]
+    pub fn build<G: PermutationGraph>(graph: &G, pattern: &[u8], additive: bool) -> Self {
+        let goal = graph.goal_arrangement();
+        let cells = goal.len();
+        let tracked = pattern
+            .iter()
+            .copied()
+            .chain(graph.pinned().into_iter().filter(|o| !pattern.contains(o)))
+            .collect::<Vec<_>>();
+
+        let table_len = table_size(tracked.len(), cells).expect("table is too large");
+        let mut state_distances = vec![UNREACHED; table_len];
+        let root = rank(&locate(&tracked, &goal), cells);
+        state_distances[root] = 0;
+        let mut queue = VecDeque::from([(root, 0)]);
+        while let Some((index, distance)) = queue.pop_front() {
+            if distance > state_distances[index] {
+                continue;
+            }
+            let mut arrangement = vec![DONT_CARE; cells];
+            for (&object, position) in tracked.iter().zip(unrank(index, tracked.len(), cells)) {
+                arrangement[position] = object;
+            }
+            for (next, mover) in graph.arrangement_moves(&arrangement) {
+                // zero-cost moves go to the front, so the queue stays sorted.
+                let cost = u8::from(!additive || pattern.contains(&mover));
+                let next_distance = distance + cost;
+                assert!(next_distance < UNREACHED, "distance doesn't fit in a byte");
+                let next_index = rank(&locate(&tracked, &next), cells);
+                if next_distance < state_distances[next_index] {
+                    state_distances[next_index] = next_distance;
+                    if cost == 0 {
+                        queue.push_front((next_index, next_distance));
+                    } else {
+                        queue.push_back((next_index, next_distance));
+                    }
+                }
+            }
+        }
+
+        let len = table_size(pattern.len(), cells).expect("table is too large");
+        let per_entry = state_distances.len() / len;
+        let mut distances = vec![UNREACHED; len];
+        for (index, &distance) in state_distances.iter().enumerate() {
+            let entry = &mut distances[index / per_entry];
+            *entry = std::cmp::min(*entry, distance);
+        }
+        Self {
+            pattern: pattern.to_vec(),
+            cells,
+            additive,
+            distances,
+        }
+    }
+
+    #[must_use]
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+
+    #[must_use]
+    pub const fn is_additive(&self) -> bool {
+        self.additive
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// The abstract distance from `arrangement` to the goal, or `i64::MAX` if
+    /// the goal can't be reached, which searchers take as a dead end.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a pattern object isn't in `arrangement`.
+    #[must_use]
+    pub fn lookup(&self, arrangement: &[u8]) -> i64 {
+        let index = rank(&locate(&self.pattern, arrangement), self.cells);
+        match self.distances[index] {
+            UNREACHED => i64::MAX,
+            distance => i64::from(distance),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns any error from `writer`, or an `InvalidInput` error if the
+    /// pattern or domain has more than 255 objects or cells, which the format
+    /// can't record.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let objects = to_byte(self.pattern.len(), "pattern length")?;
+        let cells = to_byte(self.cells, "cell count")?;
+        writer.write_all(&[objects])?;
+        writer.write_all(&self.pattern)?;
+        writer.write_all(&[cells, u8::from(self.additive)])?;
+        writer.write_all(&self.distances)
+    }
+
+    /// # Errors
+    ///
+    /// Returns any error from `reader`, or an `InvalidData` error if the
+    /// database is malformed.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut pattern = vec![0; usize::from(read_u8(reader)?)];
+        reader.read_exact(&mut pattern)?;
+        let cells = usize::from(read_u8(reader)?);
+        if pattern.len() > cells {
+            return Err(invalid_data("pattern is larger than the domain"));
+        }
+        let mut sorted = pattern.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != pattern.len() {
+            return Err(invalid_data("pattern repeats an object"));
+        }
+        let additive = match read_u8(reader)? {
+            0 => false,
+            1 => true,
+            _ => return Err(invalid_data("invalid additive flag")),
+        };
+        let len = table_size(pattern.len(), cells)
+            .ok_or_else(|| invalid_data("database is too large"))?;
+        // read what's there rather than trusting the header with an
+        // allocation, so a corrupt size runs out of data instead of memory.
+        let mut distances = Vec::new();
+        reader.take(len as u64).read_to_end(&mut distances)?;
+        if distances.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(Self {
+            pattern,
+            cells,
+            additive,
+            distances,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PdbCombination {
+    /// Sums the databases, which must be additive over disjoint patterns.
+    Sum,
+    Max,
+}
+
+/// Wraps a `PermutationGraph` to use pattern databases as its heuristic.
+#[derive(Clone, Debug)]
+pub struct PdbHeuristic<G: PermutationGraph> {
+    graph: G,
+    databases: Vec<PatternDatabase>,
+    combination: PdbCombination,
+}
+
+impl<G: PermutationGraph> PdbHeuristic<G> {
+    /// Builds additive databases over `patterns` and sums them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the patterns overlap, or under the same conditions as
+    /// `PatternDatabase::build`.
+    #[must_use]
+    pub fn additive(graph: G, patterns: &[&[u8]]) -> Self {
+        let mut objects = patterns.concat();
+        objects.sort_unstable();
+        let count = objects.len();
+        objects.dedup();
+        assert_eq!(count, objects.len(), "additive patterns must be disjoint");
+        let databases = patterns
+            .iter()
+            .map(|p| PatternDatabase::build(&graph, p, true))
+            .collect();
+        Self::from_databases(graph, databases, PdbCombination::Sum)
+    }
+
+    /// Builds a database over each of `patterns` and takes the largest
+    /// lookup.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `PatternDatabase::build`.
+    #[must_use]
+    pub fn max(graph: G, patterns: &[&[u8]]) -> Self {
+        let databases = patterns
+            .iter()
+            .map(|p| PatternDatabase::build(&graph, p, false))
+            .collect();
+        Self::from_databases(graph, databases, PdbCombination::Max)
+    }
+
+    #[must_use]
+    pub const fn from_databases(
+        graph: G,
+        databases: Vec<PatternDatabase>,
+        combination: PdbCombination,
+    ) -> Self {
+        Self {
+            graph,
+            databases,
+            combination,
+        }
+    }
+
+    #[must_use]
+    pub const fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+
+    #[must_use]
+    pub fn databases(&self) -> &[PatternDatabase] {
+        &self.databases
+    }
+
+    #[must_use]
+    pub const fn combination(&self) -> PdbCombination {
+        self.combination
+    }
+
+    /// Writes the databases to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from creating or writing the file, or an
+    /// `InvalidInput` error if there are more than 255 databases or one can't
+    /// be written by `PatternDatabase::write_to`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let count = to_byte(self.databases.len(), "database count")?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        let combination = match self.combination {
+            PdbCombination::Sum => 0,
+            PdbCombination::Max => 1,
+        };
+        writer.write_all(&[combination, count])?;
+        for database in &self.databases {
+            database.write_to(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Reads databases written by `save` for use with `graph`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from reading the file, or an `InvalidData` error if
+    /// it is malformed or was built for a different domain.
+    pub fn load(graph: G, path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a pattern database file"));
+        }
+        let combination = match read_u8(&mut reader)? {
+            0 => PdbCombination::Sum,
+            1 => PdbCombination::Max,
+            _ => return Err(invalid_data("invalid combination")),
+        };
+        let databases = (0..read_u8(&mut reader)?)
+            .map(|_| PatternDatabase::read_from(&mut reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let goal = graph.goal_arrangement();
+        let foreign = |d: &PatternDatabase| {
+            d.cells != goal.len() || d.pattern.iter().any(|o| !goal.contains(o))
+        };
+        if databases.iter().any(foreign) {
+            return Err(invalid_data("database was built for a different domain"));
+        }
+        Ok(Self::from_databases(graph, databases, combination))
+    }
+}
+
+impl<G: PermutationGraph> Graph for PdbHeuristic<G> {
+    type Node = G::Node;
+    type Edge = G::Edge;
+
+    fn root(&self) -> Self::Node {
+        self.graph.root()
+    }
+
+    fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+        self.graph.children(node)
+    }
+
+    fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+        self.graph.edges(node)
+    }
+
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        self.graph.successors(node)
+    }
+
+    fn for_each_child(&self, node: Self::Node, f: impl FnMut(Self::Node)) {
+        self.graph.for_each_child(node, f);
+    }
+
+    fn for_each_successor(&self, node: Self::Node, f: impl FnMut(Self::Edge, Self::Node)) {
+        self.graph.for_each_successor(node, f);
+    }
+
+    fn is_goal(&self, node: Self::Node) -> bool {
+        self.graph.is_goal(node)
+    }
+}
+
+impl<G: PermutationGraph + WeightedGraph<C>, C: Cost> WeightedGraph<C> for PdbHeuristic<G> {
+    fn edge_weight(&self, from: Self::Node, to: Self::Node) -> C {
+        self.graph.edge_weight(from, to)
+    }
+
+    fn weighted_successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node, C)> {
+        self.graph.weighted_successors(node)
+    }
+
+    fn for_each_weighted_successor(
+        &self,
+        node: Self::Node,
+        f: impl FnMut(Self::Edge, Self::Node, C),
+    ) {
+        self.graph.for_each_weighted_successor(node, f);
+    }
+}
+
+impl<G: PermutationGraph> HeuristicGraph for PdbHeuristic<G> {
+    fn heuristic(&self, node: Self::Node) -> i64 {
+        let arrangement = self.graph.arrangement(node);
+        let lookups = self.databases.iter().map(|d| d.lookup(&arrangement));
+        match self.combination {
+            PdbCombination::Sum => lookups.fold(0, i64::saturating_add),
+            PdbCombination::Max => lookups.max().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::bfs::BFS;
+    use crate::graphsearcher::GraphSearcher;
+    use crate::idastar::IdaStar;
+    use crate::rbfs::Rbfs;
+    use crate::tilepuzzle::SlidingPuzzle;
+
+    fn optimal(puzzle: &SlidingPuzzle) -> i64 {
+        let mut bfs = BFS::new();
+        bfs.search_tracked(puzzle, puzzle.root());
+        puzzle.path_cost(&bfs.path().unwrap())
+    }
+
+    #[test]
+    fn ranking() {
+        let positions = [4, 0, 2];
+        let r = rank(&positions, 5);
+        assert!(r < table_size(3, 5).unwrap());
+        assert_eq!(table_size(20, 255), None);
+        assert_eq!(unrank(r, 3, 5), positions);
+        assert_eq!(rank(&positions[..2], 5), r / (5 - 2));
+    }
+
+    #[test]
+    fn exact() {
+        // a database over every tile is a perfect heuristic.
+        let puzzle = SlidingPuzzle::random(3, 2, 0);
+        let pdb = PdbHeuristic::max(puzzle, &[&[1, 2, 3, 4, 5]]);
+        for seed in 0..10 {
+            let puzzle = SlidingPuzzle::random(3, 2, seed);
+            assert_eq!(pdb.heuristic(puzzle.start()), optimal(&puzzle));
+        }
+    }
+
+    #[test]
+    fn additive() {
+        let puzzle = SlidingPuzzle::random(3, 3, 0);
+        let mut pdb = PdbHeuristic::additive(puzzle, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        for seed in 0..10 {
+            let puzzle = SlidingPuzzle::random(3, 3, seed);
+            let h = pdb.heuristic(puzzle.start());
+            assert!(h >= puzzle.manhattan(puzzle.start()));
+            let optimal = optimal(&puzzle);
+            assert!(h <= optimal);
+
+            pdb =
+                PdbHeuristic::from_databases(puzzle, pdb.databases().to_vec(), PdbCombination::Sum);
+            let mut astar = AStar::new();
+            astar.search_tracked(&pdb, pdb.root());
+            assert_eq!(pdb.path_cost(&astar.path().unwrap()), optimal);
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let puzzle = SlidingPuzzle::random(3, 3, 1);
+        let pdb = PdbHeuristic::additive(puzzle.clone(), &[&[1, 2, 3], &[4, 5, 6, 7, 8]]);
+        let path = std::env::temp_dir().join(format!("graphsearch-{}.pdb", std::process::id()));
+        pdb.save(&path).unwrap();
+        let loaded = PdbHeuristic::load(puzzle.clone(), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.combination(), PdbCombination::Sum);
+        assert_eq!(loaded.databases().len(), 2);
+        assert_eq!(
+            loaded.heuristic(puzzle.start()),
+            pdb.heuristic(puzzle.start())
+        );
+        assert_eq!(loaded.databases()[1].pattern(), [4, 5, 6, 7, 8]);
+
+        let smaller = SlidingPuzzle::random(2, 2, 0);
+        let wrong = std::env::temp_dir().join(format!("graphsearch-{}-2.pdb", std::process::id()));
+        pdb.save(&wrong).unwrap();
+        let error = PdbHeuristic::load(smaller, &wrong).unwrap_err();
+        std::fs::remove_file(&wrong).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn malformed() {
+        let read = |bytes: &[u8]| {
+            PatternDatabase::read_from(&mut &bytes[..])
+                .unwrap_err()
+                .kind()
+        };
+        // 255! / 235! placements would overflow the table size.
+        let mut huge = vec![20];
+        huge.extend(0..20);
+        huge.extend([255, 0]);
+        assert_eq!(read(&huge), io::ErrorKind::InvalidData);
+        assert_eq!(read(&[2, 1, 1, 6, 0]), io::ErrorKind::InvalidData);
+        assert_eq!(read(&[1, 1, 4, 0, 3, 3]), io::ErrorKind::UnexpectedEof);
+
+        let too_many_cells = PatternDatabase {
+            pattern: vec![1],
+            cells: 256,
+            additive: false,
+            distances: Vec::new(),
+        };
+        let error = too_many_cells.write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        // a tile the 3x2 puzzle doesn't have.
+        let puzzle = SlidingPuzzle::random(3, 2, 0);
+        let foreign = PatternDatabase {
+            pattern: vec![7],
+            cells: 6,
+            additive: false,
+            distances: vec![0; 6],
+        };
+        let pdb = PdbHeuristic::from_databases(puzzle.clone(), vec![foreign], PdbCombination::Max);
+        let path = std::env::temp_dir().join(format!("graphsearch-{}-3.pdb", std::process::id()));
+        pdb.save(&path).unwrap();
+        let error = PdbHeuristic::load(puzzle, &path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn dead_ends() {
+        let puzzle = SlidingPuzzle::random(3, 2, 2);
+        let mut pdb = PdbHeuristic::max(puzzle.clone(), &[&[1, 2, 3, 4, 5]]);
+        // with every tile in the pattern, this marks a single state as unable
+        // to reach the goal.
+        let dead_end = puzzle.children(puzzle.start())[0];
+        let database = &mut pdb.databases[0];
+        let index = rank(
+            &locate(&database.pattern, &puzzle.arrangement(dead_end)),
+            database.cells,
+        );
+        database.distances[index] = UNREACHED;
+        assert_eq!(pdb.heuristic(dead_end), i64::MAX);
+
+        let result = AStar::new().search_tracked(&pdb, pdb.root());
+        assert!(result.is_found());
+        assert!(!result.path.unwrap().contains(&dead_end));
+        assert!(IdaStar::search(&pdb, pdb.root()).is_some());
+        assert!(IdaStar::new().search_tracked(&pdb, pdb.root()).is_found());
+        assert!(Rbfs::search(&pdb, pdb.root()).is_some());
+    }
+}
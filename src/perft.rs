@@ -1,20 +1,167 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use crate::graph::Graph;
 
 pub fn perft<G: Graph>(graph: &G, node: G::Node, depth: usize) -> usize {
-    let children = graph.children(node);
-    let no_children = children.is_empty();
-    if depth == 0 || no_children {
+    if depth == 0 {
+        return 1;
+    }
+    let mut count = 0;
+    graph.for_each_child(node, |c| count += perft(graph, c, depth - 1));
+    // a node without children is a leaf.
+    count.max(1)
+}
+
+/// A fixed-size, always-replace table of (node, depth) → perft count.
+#[derive(Clone, Debug)]
+pub struct PerftTable<N> {
+    entries: Vec<Option<(N, usize, usize)>>,
+}
+
+impl<N: Copy + Eq + Hash> PerftTable<N> {
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    #[must_use]
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "perft table must have at least one entry");
+        Self {
+            entries: vec![None; size],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn index(&self, node: N, depth: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        node.hash(&mut hasher);
+        depth.hash(&mut hasher);
+        (hasher.finish() % self.entries.len() as u64) as usize
+    }
+
+    fn get(&self, node: N, depth: usize) -> Option<usize> {
+        match self.entries[self.index(node, depth)] {
+            Some((n, d, count)) if n == node && d == depth => Some(count),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, node: N, depth: usize, count: usize) {
+        let index = self.index(node, depth);
+        self.entries[index] = Some((node, depth, count));
+    }
+}
+
+/// Like `perft`, but reuses subtree counts for transpositions found in `table`.
+pub fn perft_hashed<G: Graph>(
+    graph: &G,
+    node: G::Node,
+    depth: usize,
+    table: &mut PerftTable<G::Node>,
+) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+    if let Some(count) = table.get(node, depth) {
+        return count;
+    }
+    let mut count = 0;
+    graph.for_each_child(node, |c| count += perft_hashed(graph, c, depth - 1, table));
+    if count == 0 {
         return 1;
     }
-    children
-        .iter()
-        .map(|c| perft(graph, *c, depth - 1))
-        .sum::<usize>()
+    table.insert(node, depth, count);
+    count
+}
+
+/// Splits a perft count over the children of `node`, in move generation order.
+///
+/// Each child is listed with `perft(graph, child, depth - 1)`. If `depth` is 0
+/// or `node` has no children, the list is empty, even though `perft` counts
+/// `node` itself as 1 leaf. The counts sum to `perft` only when the list is not
+/// empty.
+pub fn perft_divide<G: Graph>(graph: &G, node: G::Node, depth: usize) -> Vec<(G::Node, usize)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut divided = Vec::new();
+    graph.for_each_child(node, |c| divided.push((c, perft(graph, c, depth - 1))));
+    divided
+}
+
+/// Like `perft`, but spread across `threads` scoped threads.
+///
+/// The tree is expanded a few plies until there are several subtrees per
+/// thread, and threads then claim subtrees one at a time until none are left.
+///
+/// # Panics
+///
+/// Panics if the graph panics while generating children on a worker thread.
+pub fn perft_parallel<G>(graph: &G, node: G::Node, depth: usize, threads: usize) -> usize
+where
+    G: Graph + Sync,
+    G::Node: Send + Sync,
+{
+    if depth == 0 {
+        return 1;
+    }
+    if threads <= 1 {
+        return perft(graph, node, depth);
+    }
+
+    // every subtree at the split ply has the same remaining depth.
+    let mut subtrees = vec![node];
+    let mut remaining = depth;
+    let mut leaves = 0;
+    while remaining > 1 && !subtrees.is_empty() && subtrees.len() < threads * 8 {
+        let mut next = Vec::new();
+        for n in subtrees {
+            let before = next.len();
+            graph.for_each_child(n, |c| next.push(c));
+            if next.len() == before {
+                leaves += 1;
+            }
+        }
+        subtrees = next;
+        remaining -= 1;
+    }
+
+    let claimed = AtomicUsize::new(0);
+    let counted = std::thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut count = 0;
+                    loop {
+                        let index = claimed.fetch_add(1, Ordering::Relaxed);
+                        let Some(&subtree) = subtrees.get(index) else {
+                            return count;
+                        };
+                        count += perft(graph, subtree, remaining);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|w| w.join().expect("perft worker panicked"))
+            .sum::<usize>()
+    });
+    leaves + counted
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{examplegraph::get_example_graph, graph::Graph, perft::perft};
+    use crate::{
+        examplegraph::{get_example_graph, Nim},
+        graph::Graph,
+        perft::{perft, perft_divide, perft_hashed, perft_parallel, PerftTable},
+    };
 
     #[test]
     fn depth1() {
@@ -22,4 +169,49 @@ mod tests {
         let node_count = perft(&graph, graph.root(), 1);
         assert_eq!(node_count, 2);
     }
+
+    #[test]
+    fn hashed() {
+        // a tiny table forces collisions and replacements.
+        let mut table = PerftTable::new(7);
+        for depth in 0..=10 {
+            assert_eq!(
+                perft_hashed(&Nim, Nim.root(), depth, &mut table),
+                perft(&Nim, Nim.root(), depth)
+            );
+        }
+    }
+
+    #[test]
+    fn divide() {
+        assert!(perft_divide(&Nim, Nim.root(), 0).is_empty());
+        // no stones left, so no moves to divide between.
+        assert!(perft_divide(&Nim, 0, 2).is_empty());
+        assert_eq!(perft(&Nim, 0, 2), 1);
+        for depth in 1..=6 {
+            let divided = perft_divide(&Nim, Nim.root(), depth);
+            let total = divided.iter().map(|(_, count)| count).sum::<usize>();
+            assert_eq!(divided.len(), 3);
+            assert_eq!(total, perft(&Nim, Nim.root(), depth));
+        }
+    }
+
+    #[test]
+    fn parallel() {
+        let graph = get_example_graph();
+        for depth in 0..=5 {
+            assert_eq!(
+                perft_parallel(&graph, graph.root(), depth, 4),
+                perft(&graph, graph.root(), depth)
+            );
+        }
+        for threads in 1..=5 {
+            for depth in 0..=12 {
+                assert_eq!(
+                    perft_parallel(&Nim, Nim.root(), depth, threads),
+                    perft(&Nim, Nim.root(), depth)
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,287 @@
+use std::fmt::Debug;
+
+use crate::budget::DepthLimit;
+use crate::graph::{HeuristicGraph, WeightedGraph};
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+/// Recursive best-first search.
+///
+/// Uses space linear in the depth of the search by remembering, for each
+/// node on the current path, only the f-value backed up from its best
+/// forgotten subtree. Like `IdaStar`, `is_visited` reports whether a node
+/// lies on the path of the most recent search.
+pub struct Rbfs<G: WeightedGraph + HeuristicGraph> {
+    path: Vec<G::Node>,
+    /// the edges between the nodes of `path`.
+    edges: Vec<G::Edge>,
+    /// the expanded nodes on the path, innermost last.
+    frames: Vec<Frame<G::Node, G::Edge>>,
+    /// the next node to expand, with its cost, f-value and f-limit.
+    pending: Option<(G::Node, i64, i64, i64)>,
+    found: bool,
+    /// the cost of `path` once the goal is found.
+    cost: i64,
+    depth_limit: DepthLimit,
+}
+
+struct Frame<N, E> {
+    f_limit: i64,
+    /// (f, cost, node, edge) for each child not on the path.
+    successors: Vec<(i64, i64, N, E)>,
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Debug for Rbfs<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rbfs")
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Rbfs<G> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            path: Vec::new(),
+            edges: Vec::new(),
+            frames: Vec::new(),
+            pending: None,
+            found: false,
+            cost: 0,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    /// The children of `node` that aren't on `path`, with their f-values raised
+    /// to at least the node's own.
+    fn successors(
+        graph: &G,
+        cost: i64,
+        f_node: i64,
+        children: &[(G::Edge, G::Node, i64)],
+        path: &[G::Node],
+    ) -> Vec<(i64, i64, G::Node, G::Edge)> {
+        children
+            .iter()
+            .filter(|(_, child, _)| !path.contains(child))
+            .map(|&(edge, child, weight)| {
+                let cost_to_child = cost + weight;
+                let f = std::cmp::max(cost_to_child.saturating_add(graph.heuristic(child)), f_node);
+                (f, cost_to_child, child, edge)
+            })
+            .collect()
+    }
+
+    /// Returns the goal if one is found within `f_limit`, otherwise the
+    /// backed-up f-value of `node`.
+    fn rbfs(
+        graph: &G,
+        node: G::Node,
+        cost: i64,
+        f_node: i64,
+        f_limit: i64,
+        path: &mut Vec<G::Node>,
+    ) -> Result<G::Node, i64> {
+        if graph.is_goal(node) {
+            return Ok(node);
+        }
+        let mut successors =
+            Self::successors(graph, cost, f_node, &graph.weighted_successors(node), path);
+        loop {
+            successors.sort_by_key(|&(f, _, _, _)| f);
+            let Some(&(best_f, best_cost, best, _)) = successors.first() else {
+                return Err(i64::MAX);
+            };
+            if best_f > f_limit || best_f == i64::MAX {
+                return Err(best_f);
+            }
+            let alternative = successors.get(1).map_or(i64::MAX, |&(f, _, _, _)| f);
+            path.push(best);
+            let limit = std::cmp::min(f_limit, alternative);
+            match Self::rbfs(graph, best, best_cost, best_f, limit, path) {
+                Ok(goal) => return Ok(goal),
+                Err(f) => successors[0].0 = f,
+            }
+            path.pop();
+        }
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Default for Rbfs<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> StepSearcher<G> for Rbfs<G> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.path = vec![root];
+        self.edges.clear();
+        self.frames.clear();
+        self.pending = Some((root, 0, graph.heuristic(root), i64::MAX));
+        self.found = false;
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        loop {
+            if let Some((node, cost, f_node, f_limit)) = self.pending.take() {
+                if graph.is_goal(node) {
+                    observer.on_goal(node);
+                    self.found = true;
+                    self.cost = cost;
+                    self.frames.clear();
+                    return None;
+                }
+                if !self.depth_limit.expands(graph, node, self.path.len() - 1) {
+                    // a frame with no successors is backed up at once.
+                    self.frames.push(Frame {
+                        f_limit,
+                        successors: Vec::new(),
+                    });
+                    continue;
+                }
+                observer.on_expand(node);
+                let children = graph.weighted_successors(node);
+                for &(_, child, _) in &children {
+                    observer.on_generate(node, child);
+                    if self.path.contains(&child) {
+                        observer.on_duplicate(child);
+                    }
+                }
+                let successors = Self::successors(graph, cost, f_node, &children, &self.path);
+                self.frames.push(Frame {
+                    f_limit,
+                    successors,
+                });
+                return Some(SearchStep {
+                    node,
+                    children: Vec::new(),
+                    frontier: self.frames.iter().map(|frame| frame.successors.len()).sum(),
+                    f: Some(f_node),
+                });
+            }
+            let frame = self.frames.last_mut()?;
+            frame.successors.sort_by_key(|&(f, _, _, _)| f);
+            let backed_up = match frame.successors.first() {
+                None => i64::MAX,
+                Some(&(best_f, _, _, _)) if best_f > frame.f_limit || best_f == i64::MAX => best_f,
+                Some(&(best_f, best_cost, best, edge)) => {
+                    let alternative = frame.successors.get(1).map_or(i64::MAX, |&(f, _, _, _)| f);
+                    let limit = std::cmp::min(frame.f_limit, alternative);
+                    self.path.push(best);
+                    self.edges.push(edge);
+                    self.pending = Some((best, best_cost, best_f, limit));
+                    continue;
+                }
+            };
+            // the subtree is over its limit, so forget it and back its f-value
+            // up to the parent. If there is no parent, the search has failed.
+            self.frames.pop();
+            let parent = self.frames.last_mut()?;
+            parent.successors[0].0 = backed_up;
+            self.edges.pop();
+            if let Some(node) = self.path.pop() {
+                observer.on_prune(node);
+            }
+        }
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        if self.found {
+            self.path.last().copied()
+        } else {
+            None
+        }
+    }
+
+    fn solution_cost(&self, _graph: &G) -> Option<i64> {
+        self.found.then_some(self.cost)
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> GraphSearcher<G> for Rbfs<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        let mut path = vec![root];
+        let f_root = graph.heuristic(root);
+        Self::rbfs(graph, root, 0, f_root, i64::MAX, &mut path).ok()
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.path.contains(&node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        if self.found {
+            Some(self.path.clone())
+        } else {
+            None
+        }
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        if self.found {
+            Some(self.edges.clone())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+    use crate::tilepuzzle::SlidingPuzzle;
+
+    #[test]
+    fn basic() {
+        let graph = get_example_graph();
+        let mut searcher = Rbfs::new();
+        let found = searcher.search_tracked(&graph, graph.root()).goal();
+        assert!(graph.is_goal(found.unwrap()));
+        assert_eq!(
+            searcher.path().unwrap(),
+            &[
+                ExampleNode::new(8),
+                ExampleNode::new(3),
+                ExampleNode::new(6),
+                ExampleNode::new(7)
+            ]
+        );
+        assert!(Rbfs::search(&graph, ExampleNode::new(10)).is_none());
+    }
+
+    #[test]
+    fn matches_astar() {
+        // RBFS re-expands subtrees it backed out of, but should still find
+        // optimal solutions.
+        for seed in 0..4 {
+            let puzzle = SlidingPuzzle::random(3, 3, seed);
+            let optimal = AStar::new().search_tracked(&puzzle, puzzle.root()).cost;
+            let mut searcher = Rbfs::new();
+            let result = searcher.search_tracked(&puzzle, puzzle.root());
+            assert_eq!(result.cost, optimal);
+            let path = result.path.unwrap();
+            assert_eq!(Some(puzzle.path_cost(&path)), optimal);
+        }
+    }
+}
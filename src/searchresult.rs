@@ -0,0 +1,154 @@
+use std::fmt::Debug;
+use std::time::Duration;
+
+use crate::budget::SearchOutcome;
+use crate::cost::Cost;
+use crate::graph::Graph;
+
+/// Measurements of a search.
+///
+/// Every searcher is measured the same way, from the callbacks it makes to a
+/// `SearchObserver` and the frontier size after each step, so the numbers are
+/// comparable between searchers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    pub expanded: usize,
+    pub generated: usize,
+    pub duplicates: usize,
+    pub reopened: usize,
+    /// The most nodes waiting to be expanded at once, counting the root.
+    pub max_frontier: usize,
+    pub elapsed: Duration,
+    /// The branching factor a uniform tree as deep as the solution would need
+    /// to hold as many nodes as were generated. `None` if there is no solution
+    /// or the root is the goal.
+    pub branching_factor: Option<f64>,
+}
+
+/// The effective branching factor b* for `generated` nodes and a solution at
+/// `depth`, the root of 1 + b* + b*^2 + ... + b*^depth = generated + 1.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap
+)]
+pub(crate) fn branching_factor(generated: usize, depth: usize) -> Option<f64> {
+    if depth == 0 {
+        return None;
+    }
+    let nodes = (generated + 1) as f64;
+    let tree_size = |b: f64| (0..=depth as i32).map(|i| b.powi(i)).sum::<f64>();
+    let (mut low, mut high) = (0.0, nodes.max(1.0));
+    for _ in 0..64 {
+        let mid = f64::midpoint(low, high);
+        if tree_size(mid) < nodes {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some(f64::midpoint(low, high))
+}
+
+/// Everything a searcher learned from one search.
+pub struct SearchResult<G: Graph, C = i64> {
+    pub outcome: SearchOutcome<G::Node>,
+    /// The nodes from the root to the goal, if one was found.
+    pub path: Option<Vec<G::Node>>,
+    /// The edges taken along `path`, such as the moves of a puzzle solution.
+    pub edges: Option<Vec<G::Edge>>,
+    /// The cost of `path`, for searchers that weigh edges.
+    pub cost: Option<C>,
+    pub stats: SearchStats,
+}
+
+impl<G: Graph, C> SearchResult<G, C> {
+    /// The goal, if one was found.
+    #[must_use]
+    pub const fn goal(&self) -> Option<G::Node> {
+        match self.outcome {
+            SearchOutcome::Found(goal) => Some(goal),
+            SearchOutcome::NotFound
+            | SearchOutcome::Exhausted(_)
+            | SearchOutcome::Overflow(_)
+            | SearchOutcome::NegativeCycle(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_found(&self) -> bool {
+        matches!(self.outcome, SearchOutcome::Found(_))
+    }
+}
+
+impl<G: Graph, C: Cost> Clone for SearchResult<G, C> {
+    fn clone(&self) -> Self {
+        Self {
+            outcome: self.outcome,
+            path: self.path.clone(),
+            edges: self.edges.clone(),
+            cost: self.cost,
+            stats: self.stats,
+        }
+    }
+}
+
+impl<G: Graph, C: Cost> Debug for SearchResult<G, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SearchResult")
+            .field("outcome", &self.outcome)
+            .field("path", &self.path)
+            .field("edges", &self.edges)
+            .field("cost", &self.cost)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::bfs::BFS;
+    use crate::dfs::IterDeepening;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+    use crate::graphsearcher::GraphSearcher;
+
+    #[test]
+    fn effective_branching_factor() {
+        assert_eq!(branching_factor(5, 0), None);
+        // a binary tree three deep has 14 nodes below the root.
+        let b = branching_factor(14, 3).unwrap();
+        assert!((b - 2.0).abs() < 1e-9);
+        let b = branching_factor(3, 3).unwrap();
+        assert!((b - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn results() {
+        let graph = get_example_graph();
+        let result = BFS::new().search_tracked(&graph, graph.root());
+        assert_eq!(result.goal(), Some(ExampleNode::new(7)));
+        assert_eq!(result.path.as_ref().map(Vec::len), Some(4));
+        assert_eq!(result.cost, None);
+        let stats = result.stats;
+        assert_eq!(
+            (stats.expanded, stats.generated, stats.max_frontier),
+            (7, 8, 3)
+        );
+        assert!(stats.branching_factor.is_some());
+
+        let result = AStar::new().search_tracked(&graph, graph.root());
+        assert_eq!(result.cost, Some(3));
+
+        // each iteration expands the shallower nodes again.
+        let result = IterDeepening::new().search_tracked(&graph, graph.root());
+        assert!(result.stats.expanded > stats.expanded);
+
+        let result = BFS::new().search_tracked(&graph, ExampleNode::new(10));
+        assert_eq!(result.outcome, SearchOutcome::NotFound);
+        assert!(result.path.is_none());
+        assert!(result.stats.branching_factor.is_none());
+    }
+}
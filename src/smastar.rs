@@ -0,0 +1,441 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+use crate::budget::DepthLimit;
+use crate::graph::{HeuristicGraph, WeightedGraph};
+use crate::graphsearcher::{GraphSearcher, SearchStep, StepSearcher};
+use crate::observer::SearchObserver;
+use crate::searchresult::SearchResult;
+
+struct SmaNode<N, E> {
+    node: N,
+    parent: Option<usize>,
+    /// the edge from the parent.
+    edge: Option<E>,
+    cost: i64,
+    f: i64,
+    depth: usize,
+    children: Vec<usize>,
+    /// successors still to be generated for the first time, last one next.
+    unexpanded: Vec<(E, N, i64)>,
+    /// successors dropped from memory, with their backed-up f-values.
+    forgotten: Vec<((E, N, i64), i64)>,
+    /// this node's key in the open set, if it's queued.
+    queued: Option<OpenKey>,
+}
+
+/// (f, deepest first, id), so the first key is the best node to expand and
+/// the last is the worst candidate for pruning.
+type OpenKey = (i64, Reverse<usize>, usize);
+
+/// Simplified memory-bounded A*.
+///
+/// Behaves like A* until `memory_limit` nodes are held, then drops the
+/// shallowest of the worst leaves, remembering its f-value in its parent so
+/// the subtree can be regenerated if it becomes promising again. Solutions
+/// deeper than the memory limit cannot be found.
+pub struct SmaStar<G: WeightedGraph + HeuristicGraph> {
+    memory_limit: usize,
+    nodes: Vec<Option<SmaNode<G::Node, G::Edge>>>,
+    free: Vec<usize>,
+    open: BTreeSet<OpenKey>,
+    max_nodes: usize,
+    solution: Option<usize>,
+    depth_limit: DepthLimit,
+}
+
+impl<G: WeightedGraph + HeuristicGraph> Debug for SmaStar<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SmaStar")
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> SmaStar<G> {
+    /// # Panics
+    ///
+    /// Panics if `memory_limit` is less than 2.
+    #[must_use]
+    pub fn new(memory_limit: usize) -> Self {
+        assert!(memory_limit >= 2, "memory limit must be at least 2 nodes");
+        Self {
+            memory_limit,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            open: BTreeSet::new(),
+            max_nodes: 0,
+            solution: None,
+            depth_limit: DepthLimit::new(),
+        }
+    }
+
+    /// The largest number of nodes held in memory at once.
+    #[must_use]
+    pub const fn max_nodes(&self) -> usize {
+        self.max_nodes
+    }
+
+    const fn live(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    fn get(&self, id: usize) -> &SmaNode<G::Node, G::Edge> {
+        self.nodes[id].as_ref().expect("live node")
+    }
+
+    fn get_mut(&mut self, id: usize) -> &mut SmaNode<G::Node, G::Edge> {
+        self.nodes[id].as_mut().expect("live node")
+    }
+
+    fn allocate(
+        &mut self,
+        graph: &G,
+        node: G::Node,
+        parent: Option<(usize, G::Edge)>,
+        cost: i64,
+        f: i64,
+    ) -> usize {
+        let (parent, edge) = parent.unzip();
+        let mut unexpanded = graph.weighted_successors(node);
+        unexpanded.reverse();
+        let node = SmaNode {
+            node,
+            parent,
+            edge,
+            cost,
+            f,
+            depth: parent.map_or(0, |p| self.get(p).depth + 1),
+            children: Vec::new(),
+            unexpanded,
+            forgotten: Vec::new(),
+            queued: None,
+        };
+        let id = if let Some(id) = self.free.pop() {
+            self.nodes[id] = Some(node);
+            id
+        } else {
+            self.nodes.push(Some(node));
+            self.nodes.len() - 1
+        };
+        if let Some(p) = parent {
+            self.get_mut(p).children.push(id);
+        }
+        self.max_nodes = std::cmp::max(self.max_nodes, self.live());
+        self.requeue(id);
+        id
+    }
+
+    /// Puts a node in the open set, or takes it out, according to its state.
+    ///
+    /// Leaves and nodes with successors still to generate are queued by their
+    /// own f-value. Otherwise, a node is queued by its best forgotten
+    /// successor, as that is the next one it would regenerate.
+    fn requeue(&mut self, id: usize) {
+        let n = self.get(id);
+        let f = if !n.unexpanded.is_empty() || n.children.is_empty() {
+            Some(n.f)
+        } else {
+            n.forgotten.iter().map(|&(_, f)| f).min()
+        };
+        let key = f.map(|f| (f, Reverse(n.depth), id));
+        let old = n.queued;
+        if key != old {
+            if let Some(old) = old {
+                self.open.remove(&old);
+            }
+            if let Some(key) = key {
+                self.open.insert(key);
+            }
+            self.get_mut(id).queued = key;
+        }
+    }
+
+    fn on_path(&self, id: usize, node: G::Node) -> bool {
+        let mut current = Some(id);
+        while let Some(i) = current {
+            let n = self.get(i);
+            if n.node == node {
+                return true;
+            }
+            current = n.parent;
+        }
+        false
+    }
+
+    /// Once every successor of a node has been generated, its f-value becomes
+    /// the smallest f among them, and the change is propagated upwards.
+    fn backup(&mut self, mut id: usize) {
+        loop {
+            let n = self.get(id);
+            if !n.unexpanded.is_empty() {
+                return;
+            }
+            let best = n
+                .children
+                .iter()
+                .map(|&c| self.get(c).f)
+                .chain(n.forgotten.iter().map(|&(_, f)| f))
+                .min()
+                .unwrap_or(i64::MAX);
+            if best == n.f {
+                return;
+            }
+            let parent = n.parent;
+            self.get_mut(id).f = best;
+            self.requeue(id);
+            match parent {
+                Some(p) => id = p,
+                None => return,
+            }
+        }
+    }
+
+    /// Drops the shallowest of the highest-f leaves, other than `keep`.
+    fn prune<O: SearchObserver<G>>(&mut self, keep: usize, observer: &mut O) -> bool {
+        let worst = self.open.iter().rev().map(|&(_, _, id)| id).find(|&id| {
+            id != keep && self.get(id).children.is_empty() && self.get(id).parent.is_some()
+        });
+        let Some(worst) = worst else {
+            return false;
+        };
+        if let Some(key) = self.get(worst).queued {
+            self.open.remove(&key);
+        }
+        let removed = self.nodes[worst].take().expect("live node");
+        observer.on_prune(removed.node);
+        self.free.push(worst);
+        let parent = removed.parent.expect("pruned node has a parent");
+        let p = self.get_mut(parent);
+        p.children.retain(|&c| c != worst);
+        let edge = removed.edge.expect("pruned node has an edge");
+        let weight = removed.cost - p.cost;
+        p.forgotten.push(((edge, removed.node, weight), removed.f));
+        self.requeue(parent);
+        true
+    }
+
+    /// Generates the next successor of `id`, pruning a leaf if memory is full.
+    /// Successors are generated for the first time before any are regenerated.
+    fn generate<O: SearchObserver<G>>(&mut self, graph: &G, id: usize, observer: &mut O) {
+        let n = self.get(id);
+        let (node, cost, f, depth) = (n.node, n.cost, n.f, n.depth);
+        let successor = if n.unexpanded.is_empty() {
+            n.forgotten
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &(_, f))| f)
+                .map(|(i, _)| i)
+                .map(|i| self.get_mut(id).forgotten.swap_remove(i))
+        } else {
+            self.get_mut(id)
+                .unexpanded
+                .pop()
+                .map(|child| (child, i64::MIN))
+        };
+
+        if let Some(((_, child, _), _)) = successor {
+            observer.on_generate(node, child);
+        }
+        let successor = successor.filter(|&((_, c, _), _)| {
+            let on_path = self.on_path(id, c);
+            if on_path {
+                observer.on_duplicate(c);
+            }
+            !on_path
+        });
+        if let Some(((edge, child, weight), remembered)) = successor {
+            let cost_to_child = cost + weight;
+            let f_child = if !graph.is_goal(child) && depth + 2 >= self.memory_limit {
+                // there's no room left to extend this path.
+                i64::MAX
+            } else if !self.depth_limit.allows(depth + 1) {
+                self.depth_limit.cut();
+                i64::MAX
+            } else {
+                cost_to_child
+                    .saturating_add(graph.heuristic(child))
+                    .max(f)
+                    .max(remembered)
+            };
+            if self.live() >= self.memory_limit && !self.prune(id, observer) {
+                observer.on_prune(child);
+                self.get_mut(id)
+                    .forgotten
+                    .push(((edge, child, weight), f_child));
+            } else {
+                self.allocate(graph, child, Some((id, edge)), cost_to_child, f_child);
+            }
+        }
+
+        self.requeue(id);
+        self.backup(id);
+    }
+}
+
+/// Each step generates a single successor of the best node, so a node with
+/// several children is expanded over several steps.
+impl<G: WeightedGraph + HeuristicGraph> StepSearcher<G> for SmaStar<G> {
+    fn start(&mut self, graph: &G, root: G::Node) {
+        self.depth_limit.reset();
+        self.nodes.clear();
+        self.free.clear();
+        self.open.clear();
+        self.max_nodes = 0;
+        self.solution = None;
+
+        self.allocate(graph, root, None, 0, graph.heuristic(root));
+    }
+
+    fn step_observed<O: SearchObserver<G>>(
+        &mut self,
+        graph: &G,
+        observer: &mut O,
+    ) -> Option<SearchStep<G::Node>> {
+        let &(f, _, id) = self.open.first()?;
+        if f == i64::MAX {
+            return None;
+        }
+        let node = self.get(id).node;
+        if graph.is_goal(node) {
+            observer.on_goal(node);
+            self.solution = Some(id);
+            return None;
+        }
+        observer.on_expand(node);
+        self.generate(graph, id, observer);
+        Some(SearchStep {
+            node,
+            children: Vec::new(),
+            frontier: self.open.len(),
+            f: Some(f),
+        })
+    }
+
+    fn solution(&self) -> Option<G::Node> {
+        self.solution.map(|id| self.get(id).node)
+    }
+
+    fn solution_cost(&self, _graph: &G) -> Option<i64> {
+        self.solution.map(|id| self.get(id).cost)
+    }
+
+    fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.depth_limit.set(max_depth);
+    }
+
+    fn depth_cut_off(&self) -> bool {
+        self.depth_limit.cut_off()
+    }
+}
+
+impl<G: WeightedGraph + HeuristicGraph> GraphSearcher<G> for SmaStar<G> {
+    fn search_tracked(&mut self, graph: &G, root: G::Node) -> SearchResult<G> {
+        self.search_observed(graph, root, &mut ())
+    }
+
+    fn search(graph: &G, root: G::Node) -> Option<G::Node> {
+        Self::new(usize::MAX).search_tracked(graph, root).goal()
+    }
+
+    fn is_visited(&self, node: G::Node) -> bool {
+        self.nodes.iter().flatten().any(|n| n.node == node)
+    }
+
+    fn path(&self) -> Option<Vec<G::Node>> {
+        let mut path = Vec::new();
+        let mut current = Some(self.solution?);
+        while let Some(id) = current {
+            let n = self.get(id);
+            path.push(n.node);
+            current = n.parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn path_edges(&self) -> Option<Vec<G::Edge>> {
+        let mut edges = Vec::new();
+        let mut current = Some(self.solution?);
+        while let Some(id) = current {
+            let n = self.get(id);
+            edges.extend(n.edge);
+            current = n.parent;
+        }
+        edges.reverse();
+        Some(edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::examplegraph::{get_example_graph, ExampleNode};
+    use crate::graph::Graph;
+    use crate::observer::CountingObserver;
+    use crate::tilepuzzle::{SlidingPuzzle, TileState};
+    use std::collections::HashSet;
+
+    #[test]
+    fn basic() {
+        let graph = get_example_graph();
+        for memory_limit in [4, 5, 100] {
+            let mut searcher = SmaStar::new(memory_limit);
+            let found = searcher.search_tracked(&graph, graph.root()).goal();
+            assert!(graph.is_goal(found.unwrap()));
+            assert_eq!(
+                searcher.path().unwrap(),
+                &[
+                    ExampleNode::new(8),
+                    ExampleNode::new(3),
+                    ExampleNode::new(6),
+                    ExampleNode::new(7)
+                ]
+            );
+            assert!(searcher.max_nodes() <= memory_limit);
+        }
+    }
+
+    /// Counts moves generated again after their first time.
+    #[derive(Default)]
+    struct Regenerated {
+        seen: HashSet<(TileState, TileState)>,
+        again: usize,
+    }
+
+    impl SearchObserver<SlidingPuzzle> for Regenerated {
+        fn on_generate(&mut self, parent: TileState, child: TileState) {
+            if !self.seen.insert((parent, child)) {
+                self.again += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn forgets_and_regenerates() {
+        for seed in 0..4 {
+            let puzzle = SlidingPuzzle::random(3, 3, seed);
+            let optimal = AStar::new().search_tracked(&puzzle, puzzle.root()).cost;
+            // just enough room for the solution path and a few alternatives.
+            let memory_limit = usize::try_from(optimal.unwrap()).unwrap() + 4;
+            let mut searcher = SmaStar::new(memory_limit);
+            let mut observer = (CountingObserver::new(), Regenerated::default());
+            let result = searcher.search_observed(&puzzle, puzzle.root(), &mut observer);
+            assert_eq!(result.cost, optimal);
+            assert!(observer.0.pruned > 0);
+            assert!(observer.1.again > 0);
+            assert!(searcher.max_nodes() <= memory_limit);
+        }
+    }
+
+    #[test]
+    fn too_little_memory() {
+        let graph = get_example_graph();
+        let mut searcher = SmaStar::new(3);
+        assert!(searcher
+            .search_tracked(&graph, graph.root())
+            .goal()
+            .is_none());
+        assert!(searcher.path().is_none());
+    }
+}
@@ -0,0 +1,565 @@
+use std::fmt::{Debug, Display};
+
+use crate::graph::{Graph, HeuristicGraph, WeightedGraph};
+use crate::mcts::SplitMix64;
+use crate::patterndb::PermutationGraph;
+
+/// The most cells a packed `TileState` can hold, enough for the 24-puzzle.
+pub const MAX_CELLS: usize = 25;
+
+const BITS: usize = 5;
+const MASK: u128 = (1 << BITS) - 1;
+/// The blank is stored as all ones, so that unused cells (all zeros) mark the
+/// end of the state.
+const BLANK: u128 = MASK;
+
+/// A puzzle position packed five bits per cell, row by row. Tile 0 is the
+/// blank.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileState(u128);
+
+impl TileState {
+    /// # Panics
+    ///
+    /// Panics if there are more than `MAX_CELLS` tiles, or a tile doesn't fit
+    /// in a cell.
+    #[must_use]
+    pub fn from_tiles(tiles: &[u8]) -> Self {
+        assert!(tiles.len() <= MAX_CELLS, "too many tiles");
+        let mut packed = 0;
+        for (position, &tile) in tiles.iter().enumerate() {
+            assert!(usize::from(tile) < MAX_CELLS, "tile {tile} is too large");
+            let value = if tile == 0 { BLANK } else { u128::from(tile) };
+            packed |= value << (position * BITS);
+        }
+        Self(packed)
+    }
+
+    /// The tile at `position`, 0 for the blank.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn tile(self, position: usize) -> u8 {
+        match (self.0 >> (position * BITS)) & MASK {
+            BLANK => 0,
+            tile => tile as u8,
+        }
+    }
+
+    #[must_use]
+    pub const fn len(self) -> usize {
+        (128 - self.0.leading_zeros() as usize).div_ceil(BITS)
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub fn tiles(self) -> Vec<u8> {
+        (0..self.len())
+            .map(|position| self.tile(position))
+            .collect()
+    }
+
+    /// The position of the blank.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state has no blank.
+    #[must_use]
+    pub fn blank(self) -> usize {
+        (0..self.len())
+            .find(|&position| self.tile(position) == 0)
+            .expect("state has a blank")
+    }
+
+    const fn swap(self, a: usize, b: usize) -> Self {
+        let (a, b) = (a * BITS, b * BITS);
+        let diff = ((self.0 >> a) ^ (self.0 >> b)) & MASK;
+        Self(self.0 ^ (diff << a) ^ (diff << b))
+    }
+}
+
+impl Display for TileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for position in 0..self.len() {
+            if position > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", self.tile(position))?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for TileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TileState({self})")
+    }
+}
+
+/// The direction the blank moves in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Slide {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Slide {
+    pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+}
+
+impl Display for Slide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Up => "U",
+            Self::Down => "D",
+            Self::Left => "L",
+            Self::Right => "R",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TileHeuristic {
+    Manhattan,
+    /// Manhattan distance plus two moves for every tile that must leave its
+    /// goal row or column to let another tile past.
+    LinearConflict,
+}
+
+/// The sliding-tile puzzle on a `width` by `height` board, with unit move
+/// costs. The goal has the blank in the top-left corner followed by the tiles
+/// in order, as in Korf's benchmark instances.
+#[derive(Clone, Debug)]
+pub struct SlidingPuzzle {
+    width: usize,
+    height: usize,
+    start: TileState,
+    goal: TileState,
+    heuristic: TileHeuristic,
+}
+
+impl SlidingPuzzle {
+    /// Creates a puzzle using the linear-conflict heuristic.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either side is shorter than 2, the board has more than
+    /// `MAX_CELLS` cells, or `start` isn't an arrangement of its tiles.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(width: usize, height: usize, start: TileState) -> Self {
+        assert!(width >= 2 && height >= 2, "board must be at least 2x2");
+        let cells = width * height;
+        assert!(cells <= MAX_CELLS, "board has more than {MAX_CELLS} cells");
+        let mut tiles = start.tiles();
+        tiles.sort_unstable();
+        assert!(
+            tiles.iter().copied().eq(0..cells as u8),
+            "start {start} is not an arrangement of {cells} tiles"
+        );
+        Self {
+            width,
+            height,
+            start,
+            goal: TileState::from_tiles(&(0..cells as u8).collect::<Vec<_>>()),
+            heuristic: TileHeuristic::LinearConflict,
+        }
+    }
+
+    /// Creates a puzzle whose start is a uniformly random solvable position.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as `new`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn random(width: usize, height: usize, seed: u64) -> Self {
+        let cells = width * height;
+        let mut rng = SplitMix64::new(seed);
+        let mut tiles = (0..cells as u8).collect::<Vec<_>>();
+        for i in (1..cells).rev() {
+            tiles.swap(i, rng.below(i + 1));
+        }
+        let mut puzzle = Self::new(width, height, TileState::from_tiles(&tiles));
+        if !puzzle.is_solvable(puzzle.start) {
+            // swapping two tiles flips the permutation parity.
+            let mut others = (0..cells).filter(|&p| tiles[p] != 0);
+            let (a, b) = (others.next().unwrap(), others.next().unwrap());
+            puzzle.start = puzzle.start.swap(a, b);
+        }
+        puzzle
+    }
+
+    #[must_use]
+    pub const fn with_heuristic(mut self, heuristic: TileHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub const fn start(&self) -> TileState {
+        self.start
+    }
+
+    #[must_use]
+    pub const fn goal(&self) -> TileState {
+        self.goal
+    }
+
+    pub const fn set_start(&mut self, start: TileState) {
+        self.start = start;
+    }
+
+    /// Whether the goal can be reached from `state`. Every move is a
+    /// transposition with the blank that moves the blank one cell, so the
+    /// permutation parity must match the parity of the blank's distance from
+    /// its goal cell.
+    #[must_use]
+    pub fn is_solvable(&self, state: TileState) -> bool {
+        let tiles = state.tiles();
+        let mut seen = vec![false; tiles.len()];
+        let mut cycles = 0;
+        for start in 0..tiles.len() {
+            if !seen[start] {
+                cycles += 1;
+                let mut position = start;
+                while !seen[position] {
+                    seen[position] = true;
+                    position = usize::from(tiles[position]);
+                }
+            }
+        }
+        let blank = state.blank();
+        let blank_distance = blank / self.width + blank % self.width;
+        (tiles.len() - cycles) % 2 == blank_distance % 2
+    }
+
+    /// The state reached by moving the blank, if the move stays on the board.
+    #[must_use]
+    pub fn slide(&self, state: TileState, slide: Slide) -> Option<TileState> {
+        let blank = state.blank();
+        let (x, y) = (blank % self.width, blank / self.width);
+        let target = match slide {
+            Slide::Up if y > 0 => blank - self.width,
+            Slide::Down if y + 1 < self.height => blank + self.width,
+            Slide::Left if x > 0 => blank - 1,
+            Slide::Right if x + 1 < self.width => blank + 1,
+            _ => return None,
+        };
+        Some(state.swap(blank, target))
+    }
+
+    /// The sum over all tiles of their distance from their goal cells.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn manhattan(&self, state: TileState) -> i64 {
+        (0..state.len())
+            .filter(|&position| state.tile(position) != 0)
+            .map(|position| {
+                let goal = usize::from(state.tile(position));
+                let dx = (position % self.width).abs_diff(goal % self.width);
+                let dy = (position / self.width).abs_diff(goal / self.width);
+                (dx + dy) as i64
+            })
+            .sum()
+    }
+
+    /// The Manhattan distance plus two for each tile that has to leave its
+    /// line. Within a line, the tiles that can stay put are the longest run
+    /// already in goal order.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn linear_conflict(&self, state: TileState) -> i64 {
+        let (width, height) = (self.width, self.height);
+        let mut removed = 0;
+        for y in 0..height {
+            let goals = (0..width)
+                .map(|x| usize::from(state.tile(y * width + x)))
+                .filter(|&t| t != 0 && t / width == y)
+                .map(|t| t % width);
+            removed += Self::out_of_order(goals);
+        }
+        for x in 0..width {
+            let goals = (0..height)
+                .map(|y| usize::from(state.tile(y * width + x)))
+                .filter(|&t| t != 0 && t % width == x)
+                .map(|t| t / width);
+            removed += Self::out_of_order(goals);
+        }
+        self.manhattan(state) + 2 * removed as i64
+    }
+
+    /// The length of a sequence minus its longest increasing subsequence.
+    fn out_of_order(sequence: impl Iterator<Item = usize>) -> usize {
+        let sequence = sequence.collect::<Vec<_>>();
+        let mut longest = vec![1; sequence.len()];
+        for i in 0..sequence.len() {
+            for j in 0..i {
+                if sequence[j] < sequence[i] {
+                    longest[i] = longest[i].max(longest[j] + 1);
+                }
+            }
+        }
+        sequence.len() - longest.into_iter().max().unwrap_or(0)
+    }
+}
+
+impl Graph for SlidingPuzzle {
+    type Node = TileState;
+    type Edge = Slide;
+
+    fn root(&self) -> Self::Node {
+        self.start
+    }
+
+    fn children(&self, node: Self::Node) -> Vec<Self::Node> {
+        Slide::ALL
+            .iter()
+            .filter_map(|&s| self.slide(node, s))
+            .collect()
+    }
+
+    fn edges(&self, node: Self::Node) -> Vec<Self::Edge> {
+        Slide::ALL
+            .iter()
+            .copied()
+            .filter(|&s| self.slide(node, s).is_some())
+            .collect()
+    }
+
+    fn successors(&self, node: Self::Node) -> Vec<(Self::Edge, Self::Node)> {
+        Slide::ALL
+            .iter()
+            .filter_map(|&s| self.slide(node, s).map(|next| (s, next)))
+            .collect()
+    }
+
+    fn for_each_child(&self, node: Self::Node, mut f: impl FnMut(Self::Node)) {
+        for &s in &Slide::ALL {
+            if let Some(next) = self.slide(node, s) {
+                f(next);
+            }
+        }
+    }
+
+    fn for_each_successor(&self, node: Self::Node, mut f: impl FnMut(Self::Edge, Self::Node)) {
+        for &s in &Slide::ALL {
+            if let Some(next) = self.slide(node, s) {
+                f(s, next);
+            }
+        }
+    }
+
+    fn is_goal(&self, node: Self::Node) -> bool {
+        node == self.goal
+    }
+}
+
+impl WeightedGraph for SlidingPuzzle {
+    fn edge_weight(&self, _from: Self::Node, _to: Self::Node) -> i64 {
+        1
+    }
+}
+
+impl HeuristicGraph for SlidingPuzzle {
+    fn heuristic(&self, node: Self::Node) -> i64 {
+        match self.heuristic {
+            TileHeuristic::Manhattan => self.manhattan(node),
+            TileHeuristic::LinearConflict => self.linear_conflict(node),
+        }
+    }
+}
+
+impl PermutationGraph for SlidingPuzzle {
+    fn arrangement(&self, node: Self::Node) -> Vec<u8> {
+        node.tiles()
+    }
+
+    fn goal_arrangement(&self) -> Vec<u8> {
+        self.goal.tiles()
+    }
+
+    fn pinned(&self) -> Vec<u8> {
+        vec![0]
+    }
+
+    fn arrangement_moves(&self, arrangement: &[u8]) -> Vec<(Vec<u8>, u8)> {
+        let blank = arrangement
+            .iter()
+            .position(|&t| t == 0)
+            .expect("arrangement has a blank");
+        let (x, y) = (blank % self.width, blank / self.width);
+        let mut targets = Vec::with_capacity(4);
+        if y > 0 {
+            targets.push(blank - self.width);
+        }
+        if y + 1 < self.height {
+            targets.push(blank + self.width);
+        }
+        if x > 0 {
+            targets.push(blank - 1);
+        }
+        if x + 1 < self.width {
+            targets.push(blank + 1);
+        }
+        targets
+            .into_iter()
+            .map(|target| {
+                let mut next = arrangement.to_vec();
+                next.swap(blank, target);
+                (next, arrangement[target])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::AStar;
+    use crate::bfs::BFS;
+    use crate::graphsearcher::GraphSearcher;
+    use crate::idastar::IdaStar;
+
+    /// Korf's 100 random 15-puzzle instances, with their optimal solution
+    /// lengths.
+    fn korf100() -> Vec<(SlidingPuzzle, i64)> {
+        include_str!("../fixtures/korf100.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let numbers = line
+                    .split_whitespace()
+                    .map(|n| n.parse::<u8>().unwrap())
+                    .collect::<Vec<_>>();
+                let (tiles, optimal) = numbers.split_at(16);
+                let puzzle = SlidingPuzzle::new(4, 4, TileState::from_tiles(tiles));
+                (puzzle, i64::from(optimal[0]))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packing() {
+        let state = TileState::from_tiles(&[3, 1, 0, 2]);
+        assert_eq!(state.len(), 4);
+        assert_eq!(state.tiles(), [3, 1, 0, 2]);
+        assert_eq!(state.blank(), 2);
+        assert_eq!(state.to_string(), "3 1 0 2");
+        let full = TileState::from_tiles(&(0..25).rev().collect::<Vec<_>>());
+        assert_eq!(full.len(), 25);
+        assert_eq!(full.blank(), 24);
+    }
+
+    #[test]
+    fn solvability() {
+        let puzzle = SlidingPuzzle::new(3, 3, TileState::from_tiles(&[0, 1, 2, 3, 4, 5, 6, 8, 7]));
+        assert!(!puzzle.is_solvable(puzzle.start()));
+        assert!(BFS::search(&puzzle, puzzle.root()).is_none());
+        for seed in 0..100 {
+            let puzzle = SlidingPuzzle::random(3, 2, seed);
+            assert!(puzzle.is_solvable(puzzle.start()));
+            assert!(BFS::search(&puzzle, puzzle.root()).is_some());
+        }
+    }
+
+    #[test]
+    fn eight_puzzle() {
+        for seed in 0..10 {
+            let puzzle = SlidingPuzzle::random(3, 3, seed);
+            let mut bfs = BFS::new();
+            bfs.search_tracked(&puzzle, puzzle.root());
+            let optimal = puzzle.path_cost(&bfs.path().unwrap());
+            for heuristic in [TileHeuristic::Manhattan, TileHeuristic::LinearConflict] {
+                let puzzle = puzzle.clone().with_heuristic(heuristic);
+                assert!(puzzle.heuristic(puzzle.root()) <= optimal);
+                let mut astar = AStar::new();
+                astar.search_tracked(&puzzle, puzzle.root());
+                assert_eq!(puzzle.path_cost(&astar.path().unwrap()), optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_successors() {
+        let puzzle = SlidingPuzzle::random(4, 4, 3);
+        let mut state = puzzle.start();
+        for _ in 0..20 {
+            let mut streamed = Vec::new();
+            puzzle.for_each_successor(state, |slide, next| streamed.push((slide, next)));
+            assert_eq!(streamed, puzzle.successors(state));
+            let mut children = Vec::new();
+            puzzle.for_each_child(state, |next| children.push(next));
+            assert_eq!(children, puzzle.children(state));
+            state = children[children.len() / 2];
+        }
+    }
+
+    #[test]
+    fn moves() {
+        let puzzle = SlidingPuzzle::random(3, 3, 7);
+        let result = AStar::new().search_tracked(&puzzle, puzzle.root());
+        let slides = result.edges.unwrap();
+        assert_eq!(result.cost, i64::try_from(slides.len()).ok());
+        let end = slides
+            .iter()
+            .try_fold(puzzle.start(), |state, &slide| puzzle.slide(state, slide));
+        assert!(puzzle.is_goal(end.unwrap()));
+    }
+
+    #[test]
+    fn korf_instances() {
+        let instances = korf100();
+        assert_eq!(instances.len(), 100);
+        for (puzzle, optimal) in &instances {
+            let start = puzzle.start();
+            assert!(puzzle.is_solvable(start));
+            // every move changes the Manhattan distance by exactly one.
+            assert_eq!((optimal - puzzle.manhattan(start)) % 2, 0);
+            assert!(puzzle.manhattan(start) <= puzzle.linear_conflict(start));
+            assert!(puzzle.linear_conflict(start) <= *optimal);
+        }
+    }
+
+    /// Checks that IDA* solves each of `instances`, numbered from 1 as in
+    /// Korf's paper, in its optimal number of moves.
+    fn solve_korf(instances: impl IntoIterator<Item = usize>) {
+        let korf = korf100();
+        for number in instances {
+            let (puzzle, optimal) = &korf[number - 1];
+            let mut searcher = IdaStar::new();
+            let result = searcher.search_tracked(puzzle, puzzle.root());
+            assert_eq!(result.cost, Some(*optimal), "instance {number}");
+            let path = searcher.path().unwrap();
+            assert_eq!(puzzle.path_cost(&path), *optimal, "instance {number}");
+        }
+    }
+
+    #[test]
+    fn korf_easiest() {
+        // the four instances with solutions of at most 42 moves.
+        solve_korf([55, 16, 42, 79]);
+    }
+
+    #[test]
+    #[ignore = "solves all 100 instances; run with --release"]
+    fn korf_all() {
+        solve_korf(1..=100);
+    }
+}